@@ -0,0 +1,78 @@
+//! Column lists for abacus and mental abacus (珠心算) practice: a long
+//! list of numbers to add up, some of them taken away, read top to bottom.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnList {
+    /// numbers top to bottom, negative ones are subtracted
+    pub rows: Vec<i32>,
+}
+
+impl ColumnList {
+    /// running total after each row, `None` when one overflows
+    pub fn totals(&self) -> Option<Vec<i32>> {
+        let mut total = 0i32;
+        self.rows
+            .iter()
+            .map(|v| {
+                total = total.checked_add(*v)?;
+                Some(total)
+            })
+            .collect()
+    }
+
+    pub fn answer(&self) -> Option<i32> {
+        self.rows.iter().try_fold(0i32, |total, v| total.checked_add(*v))
+    }
+
+    /// whether the running total never drops below zero, as on an abacus
+    pub fn never_negative(&self) -> bool {
+        matches!(self.totals(), Some(t) if t.iter().all(|&t| t >= 0))
+    }
+
+    /// the list as an expression read left to right, `None` when empty
+    pub fn to_expr(&self) -> Option<Expr> {
+        let (first, rest) = self.rows.split_first()?;
+        let start = Single(*first);
+        Some(rest.iter().fold(start, |e, &v| {
+            let op = if v < 0 { Op::Minus } else { Op::Add };
+            Expr::join(op, e, Single(v.abs()))
+        }))
+    }
+}
+
+/// `345 - 27 + 108 = `
+impl fmt::Display for ColumnList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, v) in self.rows.iter().enumerate() {
+            match i {
+                0 => write!(f, "{}", v)?,
+                _ if *v < 0 => write!(f, " - {}", -v)?,
+                _ => write!(f, " + {}", v)?,
+            }
+        }
+        write!(f, " = ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_lists() {
+        let l = ColumnList { rows: vec![345, -27, 108, -500] };
+        assert_eq!(l.to_string(), "345 - 27 + 108 - 500 = ");
+        assert_eq!(l.totals(), Some(vec![345, 318, 426, -74]));
+        assert!(!l.never_negative());
+        assert_eq!(Some(l.to_expr().unwrap().eval()), l.answer());
+
+        let l = ColumnList { rows: vec![i32::MAX, 1] };
+        assert_eq!(l.totals(), None);
+        assert_eq!(l.answer(), None);
+        assert!(!l.never_negative());
+    }
+}
@@ -0,0 +1,154 @@
+//! Number bonds (分与合) and make-ten (凑十法) decomposition.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondSlot {
+    Whole,
+    Left,
+    Right,
+}
+
+/// `whole` splits into `left` and `right`, the `hidden` one is the blank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberBond {
+    pub whole: i32,
+    pub left: i32,
+    pub right: i32,
+    pub hidden: BondSlot,
+}
+
+impl NumberBond {
+    pub fn new(whole: i32, left: i32, hidden: BondSlot) -> Option<NumberBond> {
+        if left < 0 || left > whole {
+            return None;
+        }
+
+        Some(NumberBond {
+            whole,
+            left,
+            right: whole - left,
+            hidden,
+        })
+    }
+
+    pub fn value(&self, slot: BondSlot) -> i32 {
+        match slot {
+            BondSlot::Whole => self.whole,
+            BondSlot::Left => self.left,
+            BondSlot::Right => self.right,
+        }
+    }
+
+    pub fn answer(&self) -> i32 {
+        self.value(self.hidden)
+    }
+
+    /// the blank expressed as an equation, e.g. `8-3` for 8 = 3 + __
+    pub fn equation(&self) -> Expr {
+        match self.hidden {
+            BondSlot::Whole => Primitive(Op::Add, self.left, self.right),
+            BondSlot::Left => Primitive(Op::Minus, self.whole, self.right),
+            BondSlot::Right => Primitive(Op::Minus, self.whole, self.left),
+        }
+    }
+}
+
+/// "8=3+__"
+impl fmt::Display for NumberBond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |slot| {
+            if slot == self.hidden {
+                "__".to_string()
+            } else {
+                self.value(slot).to_string()
+            }
+        };
+        write!(f, "{}={}+{}", show(BondSlot::Whole), show(BondSlot::Left), show(BondSlot::Right))
+    }
+}
+
+/// `a + b` crossing ten, solved by splitting the smaller addend so that
+/// one part tops the bigger one up to ten: 8+5 = 8+2+3 = 10+3 = 13
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MakeTen {
+    pub a: i32,
+    pub b: i32,
+    /// the addend that stays whole
+    pub kept: i32,
+    /// the addend that is split into `to_ten` and `rest`
+    pub split: i32,
+    pub to_ten: i32,
+    pub rest: i32,
+}
+
+impl MakeTen {
+    /// both addends are single digits and the sum crosses ten
+    pub fn new(a: i32, b: i32) -> Option<MakeTen> {
+        if !(1..10).contains(&a) || !(1..10).contains(&b) || a + b <= 10 {
+            return None;
+        }
+
+        let (kept, split) = if a >= b { (a, b) } else { (b, a) };
+        Some(MakeTen {
+            a,
+            b,
+            kept,
+            split,
+            to_ten: 10 - kept,
+            rest: split - (10 - kept),
+        })
+    }
+
+    /// how the split addend breaks apart
+    pub fn bond(&self) -> NumberBond {
+        NumberBond {
+            whole: self.split,
+            left: self.to_ten,
+            right: self.rest,
+            hidden: BondSlot::Left,
+        }
+    }
+
+    /// every line of the working, from `a+b` to the sum
+    pub fn steps(&self) -> Vec<Expr> {
+        vec![
+            Primitive(Op::Add, self.a, self.b),
+            Compound(
+                Op::Add,
+                Box::new(Primitive(Op::Add, self.kept, self.to_ten)),
+                Box::new(Single(self.rest)),
+            ),
+            Primitive(Op::Add, 10, self.rest),
+            Single(self.a + self.b),
+        ]
+    }
+}
+
+impl fmt::Display for MakeTen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps = self.steps().iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", steps.join("="))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_ten() {
+        let m = MakeTen::new(8, 5).unwrap();
+        assert_eq!(m.to_string(), "8+5=8+2+3=10+3=13");
+        let m = MakeTen::new(3, 9).unwrap();
+        assert_eq!((m.kept, m.to_ten, m.rest), (9, 1, 2));
+        assert!(MakeTen::new(4, 6).is_none());
+
+        let b = NumberBond::new(8, 3, BondSlot::Right).unwrap();
+        assert_eq!(b.to_string(), "8=3+__");
+        assert_eq!(b.answer(), 5);
+        assert_eq!(b.equation().eval(), 5);
+    }
+}
@@ -0,0 +1,105 @@
+//! Bracket-insertion puzzles (添括号): `48÷6-2x3=36`, add brackets to make
+//! it true.
+//!
+//! The puzzle is an `Expr` written without the brackets `Display` would
+//! emit. It only makes a puzzle when the plain reading gives a different
+//! value and exactly one way of bracketing reaches the stated result.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use crate::operators::{chain, groupings, leaves, whole};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct BracketPuzzle {
+    pub numbers: Vec<i32>,
+    /// `ops[i]` sits between `numbers[i]` and `numbers[i + 1]`
+    pub ops: Vec<Op>,
+    pub result: i32,
+    pub answer: Expr,
+}
+
+/// operators of `e` from left to right
+pub fn operators(e: &Expr) -> Vec<Op> {
+    match e {
+        Single(_) => Vec::new(),
+        Primitive(op, _, _) => vec![*op],
+        Compound(op, l, r) => {
+            let mut out = operators(l);
+            out.push(*op);
+            out.extend(operators(r));
+            out
+        }
+    }
+}
+
+impl BracketPuzzle {
+    /// puzzle with `e` as its answer, `None` when `e` needs no brackets,
+    /// steps out of whole numbers, or more than one placement works
+    pub fn new(e: &Expr) -> Option<BracketPuzzle> {
+        let result = whole(e)?;
+        let p = BracketPuzzle {
+            numbers: leaves(e),
+            ops: operators(e),
+            result,
+            answer: e.clone(),
+        };
+
+        if whole(&p.plain()) == Some(result) {
+            return None;
+        }
+        match p.solutions().as_slice() {
+            [only] if only.to_string() == e.to_string() => Some(p),
+            _ => None,
+        }
+    }
+
+    /// the puzzle as printed, read with the usual precedence
+    pub fn plain(&self) -> Expr {
+        chain(&self.numbers, &self.ops)
+    }
+
+    /// every bracket placement that reaches the result, as written
+    pub fn solutions(&self) -> Vec<Expr> {
+        let mut seen = HashSet::new();
+        groupings(&self.numbers, &self.ops)
+            .into_iter()
+            .filter(|(_, v)| *v == self.result)
+            .map(|(e, _)| e)
+            .filter(|e| seen.insert(e.to_string()))
+            .collect()
+    }
+}
+
+/// `48÷6-2x3=36`
+impl fmt::Display for BracketPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.plain(), self.result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bracket_puzzles() {
+        let b = Box::new;
+        // 48÷(6-2)x3
+        let e = Compound(Op::Mul, b(Compound(Op::Div, b(Single(48)), b(Primitive(Op::Minus, 6, 2)))), b(Single(3)));
+        let p = BracketPuzzle::new(&e).expect("one placement");
+        assert_eq!(p.to_string(), "48÷6-2x3=36");
+        assert_eq!(p.plain().eval(), 2);
+        assert_eq!(p.solutions()[0].to_string(), "48÷(6-2)x3");
+
+        // needs no brackets
+        assert!(BracketPuzzle::new(&Compound(Op::Add, b(Single(1)), b(Primitive(Op::Mul, 2, 3)))).is_none());
+        // 2x(3x4) reads the same as 2x3x4
+        assert!(BracketPuzzle::new(&Compound(Op::Mul, b(Single(2)), b(Primitive(Op::Mul, 3, 4)))).is_none());
+
+        let e = Compound(Op::Div, b(Single(12)), b(Primitive(Op::Mul, 2, 3)));
+        assert_eq!(e.to_string(), "12÷(2x3)");
+        assert!(BracketPuzzle::new(&e).is_some());
+    }
+}
@@ -0,0 +1,126 @@
+//! Running-total chains (连续计算): start at a number and apply one step
+//! after another, `8 → +7 → x2 → -5 → ÷5`.
+//!
+//! Every step is checked against `Rules` as if it were `total op operand`:
+//! `allows` keeps differences and quotients exact and `result` keeps each
+//! running total in range, so the same generators that bound single
+//! problems bound a chain.
+
+use crate::enumerate::Rules;
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub start: i32,
+    pub steps: Vec<(Op, i32)>,
+}
+
+impl Chain {
+    /// running totals after each step, the start not included
+    pub fn totals(&self) -> Option<Vec<i32>> {
+        let mut total = self.start;
+        self.steps
+            .iter()
+            .map(|(op, n)| {
+                total = op.checked_apply(&total, n)?;
+                Some(total)
+            })
+            .collect()
+    }
+
+    pub fn answer(&self) -> Option<i32> {
+        self.totals()?.last().copied().or(Some(self.start))
+    }
+
+    /// whether every step is allowed by `rules` and every running total is
+    /// a result they accept
+    pub fn valid<R: Rules>(&self, rules: &R) -> bool {
+        let mut total = self.start;
+        for &(op, n) in &self.steps {
+            if !rules.allows(op, total, n) {
+                return false;
+            }
+            total = match op.checked_apply(&total, &n) {
+                Some(v) if rules.result(v) => v,
+                _ => return false,
+            };
+        }
+        true
+    }
+
+    /// `len` steps from `start` with operators from `ops`, each operand
+    /// drawn from `rules.operands_of(op, true)`. An operator is picked
+    /// first so each one comes up evenly. `None` when the chain runs into
+    /// a total no step can leave.
+    pub fn random<R: Rules, G: Rng>(rules: &R, start: i32, len: usize, ops: OpSet, rng: &mut G) -> Option<Chain> {
+        let mut chain = Chain { start, steps: Vec::new() };
+        let mut total = start;
+        for _ in 0..len {
+            let mut options = ops
+                .iter()
+                .map(|op| {
+                    let steps = rules
+                        .operands_of(op, true)
+                        .filter(|&n| rules.allows(op, total, n))
+                        .filter(|n| matches!(op.checked_apply(&total, n), Some(v) if rules.result(v)))
+                        .collect::<Vec<_>>();
+                    (op, steps)
+                })
+                .filter(|(_, steps)| !steps.is_empty())
+                .collect::<Vec<_>>();
+
+            // a repeat of the last operator only when nothing else fits
+            let last = chain.steps.last().map(|s| s.0);
+            if options.iter().any(|(op, _)| Some(*op) != last) {
+                options.retain(|(op, _)| Some(*op) != last);
+            }
+
+            let (op, steps) = options.choose(rng)?;
+            let n = *steps.choose(rng)?;
+            total = op.checked_apply(&total, &n)?;
+            chain.steps.push((*op, n));
+        }
+        Some(chain)
+    }
+}
+
+/// `8 → +7 → x2 → -5 → ÷5`
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start)?;
+        for (op, n) in &self.steps {
+            write!(f, " → {}{}", op, n)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Natural;
+
+    #[test]
+    fn chains() {
+        let c = Chain {
+            start: 8,
+            steps: vec![(Op::Add, 7), (Op::Mul, 2), (Op::Minus, 5), (Op::Div, 5)],
+        };
+        assert_eq!(c.to_string(), "8 → +7 → x2 → -5 → ÷5");
+        assert_eq!(c.totals(), Some(vec![15, 30, 25, 5]));
+        assert!(c.valid(&Natural(1..10)));
+        let c = Chain { start: 8, steps: vec![(Op::Div, 3)] };
+        assert!(!c.valid(&Natural(1..10)));
+
+        let mut rng = rand::thread_rng();
+        let rules = Natural(1..10);
+        for _ in 0..20 {
+            let c = Chain::random(&rules, 5, 6, OpSet::ALL, &mut rng).unwrap();
+            assert_eq!(c.steps.len(), 6);
+            assert!(c.valid(&rules), "{}", c);
+        }
+    }
+}
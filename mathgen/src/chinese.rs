@@ -0,0 +1,236 @@
+//! Chinese number words (读数写数) and place value.
+//!
+//! Digits are read in groups of four under 万 and 亿, a run of zeros
+//! inside the number is read as a single 零 and trailing zeros are not
+//! read at all: 3050020 is 三百零五万零二十.
+
+use std::fmt;
+
+const DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+/// units inside a group of four, from the ones up
+const SMALL: [&str; 4] = ["", "十", "百", "千"];
+/// names of the places, from the ones up, as in `十万位`
+pub const PLACES: [&str; 16] = [
+    "个", "十", "百", "千", "万", "十万", "百万", "千万", "亿", "十亿", "百亿", "千亿", "万亿", "十万亿", "百万亿", "千万亿",
+];
+
+/// 1 to 9999 without leading zeros
+fn group(n: i64) -> String {
+    let mut s = String::new();
+    let mut zero = false;
+    for place in (0..4).rev() {
+        let d = (n / 10i64.pow(place as u32) % 10) as usize;
+        if d == 0 {
+            zero = !s.is_empty();
+            continue;
+        }
+        if zero {
+            s.push('零');
+            zero = false;
+        }
+        s.push(DIGITS[d]);
+        s.push_str(SMALL[place]);
+    }
+    s
+}
+
+/// 1 to 99999999, the groups under 万 and the ones
+fn section(n: i64) -> String {
+    let (high, low) = (n / 10000, n % 10000);
+    let mut s = String::new();
+    if high > 0 {
+        s.push_str(&group(high));
+        s.push('万');
+    }
+    if low > 0 {
+        if high > 0 && low < 1000 {
+            s.push('零');
+        }
+        s.push_str(&group(low));
+    }
+    s
+}
+
+/// `n` in Chinese, `None` from 10^16 on. Everything above 亿 is read as
+/// one number before it, so 1200000000000 is 一万二千亿.
+pub fn to_chinese(n: i64) -> Option<String> {
+    if n < 0 {
+        return Some(format!("负{}", to_chinese(n.checked_neg()?)?));
+    }
+    if n == 0 {
+        return Some("零".to_string());
+    }
+    if n >= 10i64.pow(16) {
+        return None;
+    }
+
+    let (high, low) = (n / 100_000_000, n % 100_000_000);
+    let mut s = String::new();
+    if high > 0 {
+        s.push_str(&section(high));
+        s.push('亿');
+    }
+    if low > 0 {
+        if high > 0 && low < 10_000_000 {
+            s.push('零');
+        }
+        s.push_str(&section(low));
+    }
+
+    // 一十五 is read 十五 at the very start
+    if s.starts_with("一十") {
+        s.remove(0);
+    }
+    Some(s)
+}
+
+/// number from its Chinese reading, `两` is taken for 二. Readings that
+/// are not well formed give `None`, but a few loose ones still parse.
+pub fn from_chinese(s: &str) -> Option<i64> {
+    if let Some(rest) = s.strip_prefix('负') {
+        return from_chinese(rest)?.checked_neg();
+    }
+    if s.is_empty() {
+        return None;
+    }
+
+    let (mut total, mut section, mut digit): (i64, i64, Option<i64>) = (0, 0, None);
+    for c in s.chars() {
+        match c {
+            '零' => digit = None,
+            '两' => digit = Some(2),
+            '十' | '百' | '千' => {
+                let unit = match c {
+                    '十' => 10,
+                    '百' => 100,
+                    _ => 1000,
+                };
+                let d = match digit {
+                    Some(d) => d,
+                    None if c == '十' => 1,
+                    None => return None,
+                };
+                section = section.checked_add(d * unit)?;
+                digit = None;
+            }
+            '万' => {
+                section = section.checked_add(digit.take().unwrap_or(0))?.checked_mul(10000)?;
+            }
+            '亿' => {
+                let v = section.checked_add(digit.take().unwrap_or(0))?;
+                total = total.checked_add(v)?.checked_mul(100_000_000)?;
+                section = 0;
+            }
+            _ => {
+                if digit.is_some() {
+                    return None;
+                }
+                digit = Some(DIGITS.iter().position(|&d| d == c)? as i64);
+            }
+        }
+    }
+    total.checked_add(section)?.checked_add(digit.unwrap_or(0))
+}
+
+/// `3704215` as `3,704,215`
+// `is_multiple_of` needs Rust 1.87, plain `%` builds on older toolchains
+#[allow(clippy::manual_is_multiple_of)]
+pub fn grouped(n: i64) -> String {
+    let digits = n.abs().to_string();
+    let mut s = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            s.push(',');
+        }
+        s.push(c);
+    }
+    if n < 0 {
+        s.insert(0, '-');
+    }
+    s
+}
+
+/// digit of `n` at `place`, counting from the ones
+pub fn digit_at(n: i64, place: usize) -> i64 {
+    n.abs() / 10i64.pow(place as u32) % 10
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberProblem {
+    /// 读数: write the number in words
+    Read(i64),
+    /// 写数: write the words as digits
+    Write(i64),
+    /// name the place of the digit at `place`, which occurs once in `n`
+    PlaceValue { n: i64, place: usize },
+}
+
+impl NumberProblem {
+    pub fn answer(&self) -> String {
+        match *self {
+            NumberProblem::Read(n) => to_chinese(n).unwrap_or_default(),
+            NumberProblem::Write(n) => n.to_string(),
+            NumberProblem::PlaceValue { place, .. } => PLACES[place].to_string(),
+        }
+    }
+}
+
+/// `3,704,215 读作: `, `三百七十万四千二百一十五 写作: `,
+/// `3,704,215 中的 7 在( )位上`
+impl fmt::Display for NumberProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NumberProblem::Read(n) => write!(f, "{} 读作: ", grouped(n)),
+            NumberProblem::Write(n) => write!(f, "{} 写作: ", to_chinese(n).unwrap_or_default()),
+            NumberProblem::PlaceValue { n, place } => {
+                write!(f, "{} 中的 {} 在( )位上", grouped(n), digit_at(n, place))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chinese_numbers() {
+        let cases = [
+            (0, "零"),
+            (15, "十五"),
+            (105, "一百零五"),
+            (1010, "一千零一十"),
+            (150_000, "十五万"),
+            (3_050_020, "三百零五万零二十"),
+            (100_010, "十万零一十"),
+            (20_000_300, "二千万零三百"),
+            (130_000_000, "一亿三千万"),
+            (100_000_005, "一亿零五"),
+            (1_000_000_000_000, "一万亿"),
+            (1_200_000_000_000, "一万二千亿"),
+            (1_230_000_000_000, "一万二千三百亿"),
+            (50_300_000_000_000, "五十万三千亿"),
+            (1_000_100_000_000, "一万零一亿"),
+            (1_200_000_000_005, "一万二千亿零五"),
+            (-42, "负四十二"),
+        ];
+        for &(n, s) in cases.iter() {
+            assert_eq!(to_chinese(n).unwrap(), s);
+            assert_eq!(from_chinese(s), Some(n), "{}", s);
+        }
+        assert_eq!(from_chinese("两千"), Some(2000));
+        assert_eq!(from_chinese("三五"), None);
+
+        for n in (0..10_000_000).step_by(9973) {
+            assert_eq!(from_chinese(&to_chinese(n).unwrap()), Some(n));
+        }
+        for n in (0..10i64.pow(16)).step_by(99_999_999_977) {
+            assert_eq!(from_chinese(&to_chinese(n).unwrap()), Some(n));
+        }
+
+        assert_eq!(grouped(3_704_215), "3,704,215");
+        let p = NumberProblem::PlaceValue { n: 3_704_215, place: 5 };
+        assert_eq!(p.to_string(), "3,704,215 中的 7 在( )位上");
+        assert_eq!(p.answer(), "十万");
+    }
+}
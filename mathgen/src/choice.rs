@@ -0,0 +1,127 @@
+//! Multiple-choice versions of problems.
+//!
+//! Wrong options come from the mistakes a student would likely make on the
+//! problem itself, see `grading`, so guessing from the options alone does
+//! not work. Near values fill up when the mistakes run out.
+
+use crate::grading::{outcomes, Mistake};
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct MultipleChoice {
+    pub problem: Expr,
+    pub options: Vec<i32>,
+    /// index of the correct option
+    pub answer: usize,
+}
+
+/// `v` with two neighbouring digits swapped, every way that changes it
+fn transpositions(v: i32) -> Vec<i32> {
+    let digits = v.abs().to_string().into_bytes();
+    let mut out = Vec::new();
+    for i in 1..digits.len() {
+        if digits[i] == digits[i - 1] || (i == 1 && digits[i] == b'0') {
+            continue;
+        }
+        let mut d = digits.clone();
+        d.swap(i - 1, i);
+        if let Ok(t) = String::from_utf8(d).expect("ascii digits").parse::<i32>() {
+            out.push(t * v.signum());
+        }
+    }
+    out
+}
+
+/// plausible wrong answers to `e`, most likely first, distinct and never
+/// negative unless the answer is
+pub fn distractors(e: &Expr) -> Vec<i32> {
+    let v = e.eval();
+    let mut candidates = Vec::new();
+    for &m in &[Mistake::LeftToRight, Mistake::DroppedCarry, Mistake::MissingBorrow] {
+        candidates.extend(outcomes(e, m));
+    }
+    candidates.extend(v.checked_add(10));
+    candidates.extend(v.checked_sub(10));
+    candidates.extend(transpositions(v));
+    candidates.extend(outcomes(e, Mistake::MultiplicationFact));
+    candidates.extend(outcomes(e, Mistake::SwappedSubtraction));
+    for d in 1..=5 {
+        candidates.extend(v.checked_add(d));
+        candidates.extend(v.checked_sub(d));
+    }
+
+    let mut out: Vec<i32> = Vec::new();
+    for c in candidates {
+        if c != v && (c >= 0 || v < 0) && !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl MultipleChoice {
+    /// `e` with `count` options in random order
+    pub fn new<R: Rng + ?Sized>(e: &Expr, count: usize, rng: &mut R) -> MultipleChoice {
+        let v = e.eval();
+        let mut options = distractors(e);
+        options.truncate(count.saturating_sub(1));
+        options.push(v);
+        options.shuffle(rng);
+
+        MultipleChoice {
+            problem: e.clone(),
+            answer: options.iter().position(|&o| o == v).expect("answer is an option"),
+            options,
+        }
+    }
+
+    /// A, B, C, ... for the option at `i`
+    pub fn letter(i: usize) -> char {
+        (b'A' + i as u8) as char
+    }
+
+    pub fn is_correct(&self, choice: usize) -> bool {
+        choice == self.answer
+    }
+}
+
+/// `7x8=  A.54  B.56  C.63  D.65`
+impl fmt::Display for MultipleChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}=", self.problem)?;
+        for (i, o) in self.options.iter().enumerate() {
+            write!(f, "  {}.{}", Self::letter(i), o)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Expr::*;
+
+    #[test]
+    fn distinct_plausible_options() {
+        let e = Compound(Op::Add, Box::new(Single(2)), Box::new(Primitive(Op::Mul, 3, 4)));
+        assert_eq!(distractors(&e)[0], 20);
+
+        let e = Primitive(Op::Add, 38, 45);
+        let d = distractors(&e);
+        assert_eq!(&d[..3], &[73, 93, 38]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let mc = MultipleChoice::new(&Primitive(Op::Mul, 7, 8), 4, &mut rng);
+            assert_eq!(mc.options.len(), 4);
+            assert_eq!(mc.options[mc.answer], 56);
+            let mut sorted = mc.options.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(sorted.len(), 4);
+        }
+    }
+}
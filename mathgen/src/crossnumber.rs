@@ -0,0 +1,297 @@
+//! Cross-number puzzles (数字填字): equations written across and down a
+//! grid, crossing at shared numbers.
+//!
+//! Each equation takes five cells, `a op b = c`, and where an across and a
+//! down equation meet they must agree on the number. Some numbers are
+//! blanked; as in the pyramids, a puzzle only stands when every blank can
+//! be worked out by plain arithmetic from an equation whose other two
+//! numbers are known, so the solution is unique.
+
+use crate::enumerate::Rules;
+use crate::math::Expr::*;
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Number(i32),
+    Op(Op),
+    Equals,
+}
+
+/// `a op b = c` starting at (row, col), across or down
+#[derive(Debug, Clone)]
+pub struct Placed {
+    pub row: usize,
+    pub col: usize,
+    pub across: bool,
+    /// always a `Primitive`
+    pub expr: Expr,
+}
+
+impl Placed {
+    /// cell `i` steps along the equation
+    pub fn cell(&self, i: usize) -> Cell {
+        if self.across {
+            (self.row, self.col + i)
+        } else {
+            (self.row + i, self.col)
+        }
+    }
+
+    /// the cells of a, b and c
+    pub fn numbers(&self) -> [Cell; 3] {
+        [self.cell(0), self.cell(2), self.cell(4)]
+    }
+
+    pub fn op(&self) -> Op {
+        match self.expr {
+            Primitive(op, _, _) => op,
+            _ => panic!("placed equations are single operations"),
+        }
+    }
+
+    pub fn tokens(&self) -> Option<[Token; 5]> {
+        match self.expr {
+            Primitive(op, a, b) => Some([
+                Token::Number(a),
+                Token::Op(op),
+                Token::Number(b),
+                Token::Equals,
+                Token::Number(self.expr.checked_eval()?),
+            ]),
+            _ => None,
+        }
+    }
+}
+
+/// third number of `a op b = c` from the other two
+fn deduce(op: Op, a: Option<i32>, b: Option<i32>, c: Option<i32>) -> [Option<i32>; 3] {
+    let exact = |l: i32, r: i32| if r != 0 && l % r == 0 { Some(l / r) } else { None };
+    match (a, b, c) {
+        (Some(x), Some(y), None) => [a, b, op.checked_apply(&x, &y)],
+        (Some(x), None, Some(z)) => {
+            let y = match op {
+                Op::Add => z.checked_sub(x),
+                Op::Minus => x.checked_sub(z),
+                Op::Mul => exact(z, x),
+                Op::Div => exact(x, z),
+            };
+            [a, y, c]
+        }
+        (None, Some(y), Some(z)) => {
+            let x = match op {
+                Op::Add => z.checked_sub(y),
+                Op::Minus => z.checked_add(y),
+                Op::Mul => exact(z, y),
+                Op::Div => z.checked_mul(y),
+            };
+            [x, b, c]
+        }
+        _ => [a, b, c],
+    }
+}
+
+/// whether `a op b` is a step the rules allow, exact and with a result
+/// they accept
+fn fits<R: Rules>(rules: &R, op: Op, a: i32, b: i32) -> Option<i32> {
+    if !rules.allows(op, a, b) || (op == Op::Div && (b == 0 || a % b != 0)) {
+        return None;
+    }
+    op.checked_apply(&a, &b).filter(|&c| rules.result(c))
+}
+
+#[derive(Debug, Clone)]
+pub struct CrossNumber {
+    pub equations: Vec<Placed>,
+    /// number cells left for the solver, sorted
+    pub blanks: Vec<Cell>,
+}
+
+impl CrossNumber {
+    /// every filled cell, `None` when two equations disagree on a cell
+    pub fn grid(&self) -> Option<BTreeMap<Cell, Token>> {
+        let mut grid = BTreeMap::new();
+        for e in &self.equations {
+            for (i, t) in e.tokens()?.iter().enumerate() {
+                if *grid.entry(e.cell(i)).or_insert(*t) != *t {
+                    return None;
+                }
+            }
+        }
+        Some(grid)
+    }
+
+    /// rows and columns the grid spans
+    pub fn size(&self) -> (usize, usize) {
+        self.equations.iter().fold((0, 0), |(rows, cols), e| {
+            let (r, c) = e.cell(4);
+            (rows.max(r + 1), cols.max(c + 1))
+        })
+    }
+
+    /// the blanks worked out one equation at a time, `None` when some
+    /// stay unknown or an answer does not fit
+    pub fn solve(&self) -> Option<BTreeMap<Cell, i32>> {
+        let grid = self.grid()?;
+        let mut known = grid
+            .iter()
+            .filter(|(c, _)| !self.blanks.contains(c))
+            .filter_map(|(&c, t)| match t {
+                Token::Number(v) => Some((c, *v)),
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        loop {
+            let mut progress = false;
+            for e in &self.equations {
+                let cells = e.numbers();
+                let before = cells.map(|c| known.get(&c).copied());
+                let after = deduce(e.op(), before[0], before[1], before[2]);
+                for (cell, (b, a)) in cells.iter().zip(before.iter().zip(&after)) {
+                    if let (None, Some(v)) = (b, a) {
+                        known.insert(*cell, *v);
+                        progress = true;
+                    }
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        self.blanks
+            .iter()
+            .map(|c| match grid.get(c) {
+                Some(Token::Number(v)) if known.get(c) == Some(v) => Some((*c, *v)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// three equations across and three down on a 5 x 5 grid, crossing at
+    /// a 3 x 3 lattice of numbers, each a step allowed by `rules` with
+    /// operators from `ops`, and `blanks` numbers blanked that the solver
+    /// can still recover. `None` when the lattice does not close up in the
+    /// bottom right corner or fewer blanks fit.
+    pub fn lattice<R: Rules, G: Rng>(rules: &R, ops: OpSet, blanks: usize, rng: &mut G) -> Option<CrossNumber> {
+        let ops = ops.iter().collect::<Vec<_>>();
+        let range = rules.operands();
+        if range.start >= range.end {
+            return None;
+        }
+        let mut m = [[0; 3]; 3];
+        let mut row_ops = [Op::Add; 3];
+        let mut col_ops = [Op::Add; 3];
+
+        for r in 0..2 {
+            row_ops[r] = *ops.choose(rng)?;
+            m[r][0] = rng.gen_range(range.start, range.end);
+            m[r][1] = rng.gen_range(range.start, range.end);
+            m[r][2] = fits(rules, row_ops[r], m[r][0], m[r][1])?;
+        }
+        for c in 0..2 {
+            col_ops[c] = *ops.choose(rng)?;
+            m[2][c] = fits(rules, col_ops[c], m[0][c], m[1][c])?;
+        }
+
+        // the corner must come out the same across and down
+        let mut corners = Vec::new();
+        for &down in &ops {
+            for &across in &ops {
+                let v = fits(rules, down, m[0][2], m[1][2]);
+                if v.is_some() && v == fits(rules, across, m[2][0], m[2][1]) {
+                    corners.push((down, across, v?));
+                }
+            }
+        }
+        let &(down, across, corner) = corners.choose(rng)?;
+        col_ops[2] = down;
+        row_ops[2] = across;
+        m[2][2] = corner;
+
+        let mut equations = Vec::new();
+        for i in 0..3 {
+            let expr = Primitive(row_ops[i], m[i][0], m[i][1]);
+            equations.push(Placed { row: 2 * i, col: 0, across: true, expr });
+            let expr = Primitive(col_ops[i], m[0][i], m[1][i]);
+            equations.push(Placed { row: 0, col: 2 * i, across: false, expr });
+        }
+
+        let mut p = CrossNumber { equations, blanks: Vec::new() };
+        let mut cells = (0..9).map(|i| (i / 3 * 2, i % 3 * 2)).collect::<Vec<_>>();
+        cells.shuffle(rng);
+        for c in cells {
+            if p.blanks.len() == blanks {
+                break;
+            }
+            p.blanks.push(c);
+            p.blanks.sort();
+            if p.solve().is_none() {
+                p.blanks.retain(|&b| b != c);
+            }
+        }
+        if p.blanks.len() == blanks {
+            Some(p)
+        } else {
+            None
+        }
+    }
+}
+
+/// the grid row by row, `__` for blanks and `.` for empty cells
+impl fmt::Display for CrossNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let grid = self.grid().ok_or(fmt::Error)?;
+        let (rows, cols) = self.size();
+        for r in 0..rows {
+            for c in 0..cols {
+                let s = match grid.get(&(r, c)) {
+                    _ if self.blanks.contains(&(r, c)) => "__".to_string(),
+                    Some(Token::Number(v)) => v.to_string(),
+                    Some(Token::Op(op)) => op.to_string(),
+                    Some(Token::Equals) => "=".to_string(),
+                    None => ".".to_string(),
+                };
+                write!(f, "{:>4}", s)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Natural;
+
+    #[test]
+    fn cross_numbers() {
+        let placed = |row, col, across, expr| Placed { row, col, across, expr };
+        let mut p = CrossNumber {
+            equations: vec![
+                placed(0, 0, true, Primitive(Op::Add, 3, 4)),
+                placed(0, 4, false, Primitive(Op::Mul, 7, 2)),
+            ],
+            blanks: vec![(0, 2), (2, 4)],
+        };
+        assert_eq!(p.size(), (5, 5));
+        assert_eq!(p.solve().unwrap().into_iter().collect::<Vec<_>>(), vec![((0, 2), 4), ((2, 4), 2)]);
+        p.blanks.push((0, 0));
+        assert_eq!(p.solve(), None);
+        p.equations[1].expr = Primitive(Op::Mul, 8, 2);
+        assert_eq!(p.grid(), None);
+
+        let mut rng = rand::thread_rng();
+        let p = (0..10000).find_map(|_| CrossNumber::lattice(&Natural(1..10), OpSet::ALL, 5, &mut rng)).unwrap();
+        assert_eq!(p.blanks.len(), 5);
+        assert!(p.solve().is_some(), "{}", p);
+    }
+}
@@ -0,0 +1,188 @@
+//! Long division (除法竖式) worked layout.
+//!
+//! `LongDivision` records every step of dividing digit by digit: the number
+//! currently being divided after a bring-down, the quotient digit written
+//! above it, the product subtracted and what is left. `layout` flattens the
+//! steps into the stacked rows under the bracket, each one right aligned to
+//! a column of the dividend.
+
+use crate::math::Expr::*;
+use crate::math::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DivisionStep {
+    /// dividend column (0 = leftmost digit) of the digit just brought down
+    pub column: usize,
+    /// number being divided in this step
+    pub working: i32,
+    pub quotient_digit: u8,
+    /// `quotient_digit * divisor`, subtracted from `working`
+    pub product: i32,
+    pub remainder: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivisionRowKind {
+    /// remainder with the next digit brought down
+    Working,
+    Product,
+    /// what is left at the very end
+    Remainder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DivisionRow {
+    pub kind: DivisionRowKind,
+    pub value: i32,
+    /// dividend column the last digit of `value` sits under
+    pub column: usize,
+    /// draw a rule under this row
+    pub rule_below: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongDivision {
+    pub dividend: i32,
+    pub divisor: i32,
+    pub quotient: i32,
+    pub remainder: i32,
+    pub steps: Vec<DivisionStep>,
+}
+
+impl LongDivision {
+    pub fn new(dividend: i32, divisor: i32) -> Option<LongDivision> {
+        if dividend < 0 || divisor <= 0 {
+            return None;
+        }
+
+        let digits = dividend
+            .to_string()
+            .bytes()
+            .map(|b| (b - b'0') as i32)
+            .collect::<Vec<_>>();
+
+        let mut steps = Vec::new();
+        let mut working = 0;
+        for (column, d) in digits.iter().enumerate() {
+            working = working * 10 + d;
+            // no quotient digit until the leading digits reach the divisor
+            if steps.is_empty() && working < divisor && column + 1 < digits.len() {
+                continue;
+            }
+
+            let q = working / divisor;
+            let product = q * divisor;
+            steps.push(DivisionStep {
+                column,
+                working,
+                quotient_digit: q as u8,
+                product,
+                remainder: working - product,
+            });
+            working -= product;
+        }
+
+        Some(LongDivision {
+            dividend,
+            divisor,
+            quotient: dividend / divisor,
+            remainder: dividend % divisor,
+            steps,
+        })
+    }
+
+    pub fn from_expr(e: &Expr) -> Option<LongDivision> {
+        match e {
+            Primitive(Op::Div, v1, v2) => Self::new(*v1, *v2),
+            Compound(Op::Div, v1, v2) => match (v1.as_ref(), v2.as_ref()) {
+                (Single(l), Single(r)) => Self::new(*l, *r),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        self.dividend.to_string().len()
+    }
+
+    /// quotient digits above the dividend, `None` over the leading columns
+    pub fn quotient_cells(&self) -> Vec<Option<u8>> {
+        let mut cells = vec![None; self.columns()];
+        for s in &self.steps {
+            cells[s.column] = Some(s.quotient_digit);
+        }
+        cells
+    }
+
+    /// rows stacked under the dividend. A zero quotient digit only brings the
+    /// next digit down, so it adds no subtraction of its own.
+    pub fn layout(&self) -> Vec<DivisionRow> {
+        let mut rows = Vec::new();
+        for (i, s) in self.steps.iter().enumerate() {
+            if s.quotient_digit == 0 {
+                continue;
+            }
+            // the first working number is the dividend itself
+            if i > 0 {
+                rows.push(DivisionRow {
+                    kind: DivisionRowKind::Working,
+                    value: s.working,
+                    column: s.column,
+                    rule_below: false,
+                });
+            }
+            rows.push(DivisionRow {
+                kind: DivisionRowKind::Product,
+                value: s.product,
+                column: s.column,
+                rule_below: true,
+            });
+        }
+
+        if let Some(last) = self.steps.last() {
+            let value = if last.quotient_digit == 0 { last.working } else { last.remainder };
+            rows.push(DivisionRow {
+                kind: DivisionRowKind::Remainder,
+                value,
+                column: last.column,
+                rule_below: false,
+            });
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_division_steps() {
+        let d = LongDivision::new(864, 12).unwrap();
+        assert_eq!(d.quotient, 72);
+        assert_eq!(d.quotient_cells(), vec![None, Some(7), Some(2)]);
+        let rows = d.layout().iter().map(|r| (r.kind, r.value, r.column)).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                (DivisionRowKind::Product, 84, 1),
+                (DivisionRowKind::Working, 24, 2),
+                (DivisionRowKind::Product, 24, 2),
+                (DivisionRowKind::Remainder, 0, 2),
+            ]
+        );
+
+        let d = LongDivision::new(816, 8).unwrap();
+        assert_eq!(d.quotient_cells(), vec![Some(1), Some(0), Some(2)]);
+        assert_eq!(d.layout()[1].value, 16);
+
+        let d = LongDivision::new(801, 8).unwrap();
+        assert_eq!(d.remainder, 1);
+        assert_eq!(d.layout().last().map(|r| r.value), Some(1));
+
+        let d = LongDivision::new(5, 7).unwrap();
+        assert_eq!(d.quotient_cells(), vec![Some(0)]);
+        assert_eq!(d.layout().last().map(|r| r.value), Some(5));
+    }
+}
@@ -0,0 +1,209 @@
+//! Exhaustive enumeration of expressions.
+//!
+//! For small ranges every valid `Expr` of a tree shape can be listed, which
+//! gives truly uniform sampling, or just counted to learn how many distinct
+//! problems a configuration can produce at all.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// shape of an expression tree, without operators or numbers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Shape {
+    Leaf,
+    Node(Box<Shape>, Box<Shape>),
+}
+
+impl Shape {
+    pub fn node(l: Shape, r: Shape) -> Shape {
+        Shape::Node(Box::new(l), Box::new(r))
+    }
+
+    pub fn leaves(&self) -> usize {
+        match self {
+            Shape::Leaf => 1,
+            Shape::Node(l, r) => l.leaves() + r.leaves(),
+        }
+    }
+
+    /// every shape with `leaves` operands
+    pub fn all(leaves: usize) -> Vec<Shape> {
+        if leaves <= 1 {
+            return vec![Shape::Leaf];
+        }
+
+        let mut shapes = Vec::new();
+        for n in 1..leaves {
+            for l in Shape::all(n) {
+                for r in Shape::all(leaves - n) {
+                    shapes.push(Shape::node(l.clone(), r));
+                }
+            }
+        }
+        shapes
+    }
+
+    pub fn of<N: Number>(e: &Expr<N>) -> Shape {
+        match e {
+            Single(_) => Shape::Leaf,
+            Primitive(..) => Shape::node(Shape::Leaf, Shape::Leaf),
+            Compound(_, l, r) => Shape::node(Shape::of(l), Shape::of(r)),
+        }
+    }
+}
+
+/// what counts as a valid expression
+pub trait Rules {
+    /// values a single operand can take
+    fn operands(&self) -> Range<i32>;
+    /// values an operand right under `op` can take, `right` for its right
+    /// hand side. Templates fill their slots from here.
+    fn operands_of(&self, _op: Op, _right: bool) -> Range<i32> {
+        self.operands()
+    }
+    /// whether `l op r` may appear anywhere in the tree
+    fn allows(&self, op: Op, l: i32, r: i32) -> bool;
+    /// whether the whole expression may evaluate to `value`
+    fn result(&self, value: i32) -> bool;
+    /// final check of the whole expression's value and the operators it
+    /// uses, only the value by default
+    fn accepts(&self, value: i32, _ops: OpSet) -> bool {
+        self.result(value)
+    }
+}
+
+fn apply<R: Rules>(rules: &R, op: Op, l: i32, r: i32) -> Option<i32> {
+    if rules.allows(op, l, r) {
+        op.checked_apply(&l, &r)
+    } else {
+        None
+    }
+}
+
+/// every tree of `shape` whose nodes are allowed, with value and operators
+fn build<R: Rules>(rules: &R, shape: &Shape) -> Vec<(Expr, i32, OpSet)> {
+    match shape {
+        Shape::Leaf => rules.operands().map(|v| (Single(v), v, OpSet::empty())).collect(),
+        Shape::Node(l, r) => {
+            let (ls, rs) = (build(rules, l), build(rules, r));
+            let mut out = Vec::new();
+            for (le, lv, lops) in &ls {
+                for (re, rv, rops) in &rs {
+                    for &op in Op::ALL.iter() {
+                        if let Some(v) = apply(rules, op, *lv, *rv) {
+                            out.push((Expr::join(op, le.clone(), re.clone()), v, lops.union(*rops).with(op)));
+                        }
+                    }
+                }
+            }
+            out
+        }
+    }
+}
+
+/// number of trees of `shape` per value and set of operators
+fn tally<R: Rules>(rules: &R, shape: &Shape) -> HashMap<(i32, OpSet), u64> {
+    let mut out = HashMap::new();
+    match shape {
+        Shape::Leaf => {
+            for v in rules.operands() {
+                out.insert((v, OpSet::empty()), 1);
+            }
+        }
+        Shape::Node(l, r) => {
+            let (ls, rs) = (tally(rules, l), tally(rules, r));
+            for (&(lv, lops), lc) in &ls {
+                for (&(rv, rops), rc) in &rs {
+                    for &op in Op::ALL.iter() {
+                        if let Some(v) = apply(rules, op, lv, rv) {
+                            *out.entry((v, lops.union(rops).with(op))).or_insert(0) += lc * rc;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// every valid expression of `shape`
+pub fn list<R: Rules>(rules: &R, shape: &Shape) -> Vec<Expr> {
+    build(rules, shape)
+        .into_iter()
+        .filter(|(_, v, ops)| rules.accepts(*v, *ops))
+        .map(|(e, _, _)| e)
+        .collect()
+}
+
+/// how many valid expressions `shape` has, without building them
+pub fn count<R: Rules>(rules: &R, shape: &Shape) -> u64 {
+    tally(rules, shape)
+        .iter()
+        .filter(|(&(v, ops), _)| rules.accepts(v, ops))
+        .map(|(_, c)| c)
+        .sum()
+}
+
+/// every valid expression with `leaves` operands, over all shapes
+pub fn list_all<R: Rules>(rules: &R, leaves: usize) -> Vec<Expr> {
+    Shape::all(leaves).iter().flat_map(|s| list(rules, s)).collect()
+}
+
+pub fn count_all<R: Rules>(rules: &R, leaves: usize) -> u64 {
+    Shape::all(leaves).iter().map(|s| count(rules, s)).sum()
+}
+
+/// how many trees `list` builds on the way, subtrees and those the final
+/// check throws out included, which bounds the memory it takes
+pub fn count_built<R: Rules>(rules: &R, shape: &Shape) -> u64 {
+    let own = tally(rules, shape).values().sum::<u64>();
+    match shape {
+        Shape::Leaf => own,
+        Shape::Node(l, r) => own + count_built(rules, l) + count_built(rules, r),
+    }
+}
+
+pub fn count_built_all<R: Rules>(rules: &R, leaves: usize) -> u64 {
+    Shape::all(leaves).iter().map(|s| count_built(rules, s)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Small;
+
+    impl Rules for Small {
+        fn operands(&self) -> Range<i32> {
+            1..4
+        }
+
+        fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+            op != Op::Div || (r != 0 && l % r == 0)
+        }
+
+        fn result(&self, value: i32) -> bool {
+            value > 0
+        }
+    }
+
+    #[test]
+    fn enumerate_small() {
+        assert_eq!(Shape::all(4).len(), 5);
+
+        let pair = Shape::node(Shape::Leaf, Shape::Leaf);
+        // 9 sums, 3 positive differences, 9 products, 5 exact quotients
+        assert_eq!(count(&Small, &pair), 26);
+        assert_eq!(list(&Small, &pair).len(), 26);
+        // 3 leaves a side, and all 9 differences before the final check
+        assert_eq!(count_built(&Small, &pair), 3 + 3 + 9 + 9 + 9 + 5);
+
+        for leaves in 2..5 {
+            let all = list_all(&Small, leaves);
+            assert_eq!(all.len() as u64, count_all(&Small, leaves));
+            assert!(all.iter().all(|e| e.eval() > 0 && Shape::of(e).leaves() == leaves));
+        }
+    }
+}
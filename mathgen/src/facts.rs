@@ -0,0 +1,103 @@
+//! Multiplication table (九九乘法表) facts and coverage.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// the fact `a x b`, its inverse division is `a*b ÷ b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fact {
+    pub a: i32,
+    pub b: i32,
+}
+
+impl Fact {
+    pub fn new(a: i32, b: i32) -> Fact {
+        Fact { a, b }
+    }
+
+    pub fn product(&self) -> i32 {
+        self.a * self.b
+    }
+
+    pub fn mul(&self) -> Expr {
+        Primitive(Op::Mul, self.a, self.b)
+    }
+
+    /// `None` when `b` is zero
+    pub fn div(&self) -> Option<Expr> {
+        if self.b == 0 {
+            None
+        } else {
+            Some(Primitive(Op::Div, self.product(), self.b))
+        }
+    }
+
+    /// the fact a problem drills, divisions map back to their multiplication
+    pub fn of(e: &Expr) -> Option<Fact> {
+        match e {
+            Primitive(Op::Mul, a, b) => Some(Fact::new(*a, *b)),
+            Primitive(Op::Div, p, b) if *b != 0 && p % b == 0 => Some(Fact::new(p / b, *b)),
+            _ => None,
+        }
+    }
+}
+
+/// every `a x b` with `a` from `a` and `b` from `b`
+pub fn table(a: Range<i32>, b: Range<i32>) -> Vec<Fact> {
+    a.flat_map(|x| b.clone().map(move |y| Fact::new(x, y))).collect()
+}
+
+/// "the 7s": `n x b` for every `b` in `by`
+pub fn times(n: i32, by: Range<i32>) -> Vec<Fact> {
+    by.map(|y| Fact::new(n, y)).collect()
+}
+
+/// the rhyme table (口诀): `a x b` with `a <= b`, so each fact shows up once
+pub fn rhyme_table(r: Range<i32>) -> Vec<Fact> {
+    table(r.clone(), r).into_iter().filter(|f| f.a <= f.b).collect()
+}
+
+/// every problem of one pass over `facts`, each fact `repeat` times, plus
+/// as many inverse divisions when `inverse` is set. Not shuffled.
+pub fn round(facts: &[Fact], repeat: usize, inverse: bool) -> Vec<Expr> {
+    let mut problems = Vec::new();
+    for f in facts {
+        for _ in 0..repeat {
+            problems.push(f.mul());
+            if inverse {
+                problems.extend(f.div());
+            }
+        }
+    }
+    problems
+}
+
+/// how often each fact is drilled by `problems`
+pub fn coverage(problems: &[Expr]) -> BTreeMap<Fact, usize> {
+    let mut cov = BTreeMap::new();
+    for f in problems.iter().filter_map(Fact::of) {
+        *cov.entry(f).or_insert(0) += 1;
+    }
+    cov
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fact_round() {
+        assert_eq!(table(2..10, 2..10).len(), 64);
+        assert_eq!(rhyme_table(1..10).len(), 45);
+
+        let sevens = times(7, 1..10);
+        let problems = round(&sevens, 2, true);
+        assert_eq!(problems.len(), 36);
+        let cov = coverage(&problems);
+        assert_eq!(cov.len(), 9);
+        assert!(cov.values().all(|&n| n == 4));
+        assert_eq!(Fact::of(&Primitive(Op::Div, 56, 8)), Some(Fact::new(7, 8)));
+    }
+}
@@ -0,0 +1,192 @@
+//! Answer checking with a guess at what went wrong.
+//!
+//! A wrong answer is compared with the values the problem takes under
+//! typical mistakes: reading it left to right, or getting a single step
+//! wrong in one of the usual ways while the rest is right. The first kind
+//! of mistake that explains the answer is reported.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mistake {
+    /// worked from left to right, ignoring `x ÷` before `+ -`
+    LeftToRight,
+    /// added the columns but not the carries
+    DroppedCarry,
+    /// borrowed ten for a column but never took it from the next one
+    MissingBorrow,
+    /// subtracted the left operand from the right one
+    SwappedSubtraction,
+    /// a multiplication or division fact off by one step
+    MultiplicationFact,
+}
+
+impl Mistake {
+    pub const ALL: [Mistake; 5] = [
+        Mistake::LeftToRight,
+        Mistake::DroppedCarry,
+        Mistake::MissingBorrow,
+        Mistake::SwappedSubtraction,
+        Mistake::MultiplicationFact,
+    ];
+}
+
+impl fmt::Display for Mistake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Mistake::LeftToRight => "运算顺序错误, 没有先乘除后加减",
+            Mistake::DroppedCarry => "加法忘记进位",
+            Mistake::MissingBorrow => "减法退位后没有减一",
+            Mistake::SwappedSubtraction => "减法被减数和减数颠倒",
+            Mistake::MultiplicationFact => "乘法口诀记错",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    Correct,
+    /// with the likely cause, if any of the known mistakes explains it
+    Incorrect(Option<Mistake>),
+}
+
+impl Grade {
+    pub fn is_correct(&self) -> bool {
+        *self == Grade::Correct
+    }
+}
+
+pub fn grade(e: &Expr, answer: i32) -> Grade {
+    if e.checked_eval() == Some(answer) {
+        Grade::Correct
+    } else {
+        Grade::Incorrect(diagnose(e, answer))
+    }
+}
+
+/// the first mistake in `Mistake::ALL` order that leads to `answer`
+pub fn diagnose(e: &Expr, answer: i32) -> Option<Mistake> {
+    if e.checked_eval() == Some(answer) {
+        return None;
+    }
+    Mistake::ALL.iter().cloned().find(|&m| outcomes(e, m).contains(&answer))
+}
+
+/// values `e` takes with mistake `m`
+pub fn outcomes(e: &Expr, m: Mistake) -> Vec<i32> {
+    match m {
+        Mistake::LeftToRight => left_to_right(&e.to_string()).into_iter().collect(),
+        _ => variants(e, &|op, l, r| faulty(m, op, l, r)),
+    }
+}
+
+/// wrong values a single step `l op r` can get with mistake `m`
+fn faulty(m: Mistake, op: Op, l: i32, r: i32) -> Vec<i32> {
+    match (m, op) {
+        (Mistake::DroppedCarry, Op::Add) if l >= 0 && r >= 0 => columns(l, r, |a, b| (a + b) % 10).into_iter().collect(),
+        (Mistake::MissingBorrow, Op::Minus) if l >= r && r >= 0 => {
+            columns(l, r, |a, b| (a + 10 - b) % 10).into_iter().collect()
+        }
+        (Mistake::SwappedSubtraction, Op::Minus) => r.checked_sub(l).into_iter().collect(),
+        (Mistake::MultiplicationFact, Op::Mul) if (1..10).contains(&l) && (1..10).contains(&r) => {
+            vec![(l - 1) * r, (l + 1) * r, l * (r - 1), l * (r + 1)]
+        }
+        (Mistake::MultiplicationFact, Op::Div) if (1..10).contains(&r) && (1..10).contains(&(l / r)) => {
+            vec![l / r - 1, l / r + 1]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `a` and `b` worked column by column with `digit`, nothing carried over
+fn columns(a: i32, b: i32, digit: fn(i64, i64) -> i64) -> Option<i32> {
+    let (mut a, mut b) = (i64::from(a), i64::from(b));
+    let (mut v, mut place) = (0, 1);
+    while a > 0 || b > 0 {
+        v += digit(a % 10, b % 10) * place;
+        a /= 10;
+        b /= 10;
+        place *= 10;
+    }
+    i32::try_from(v).ok()
+}
+
+/// values of `e` with exactly one step replaced by what `step` gives
+fn variants(e: &Expr, step: &dyn Fn(Op, i32, i32) -> Vec<i32>) -> Vec<i32> {
+    let (op, l, r) = match e {
+        Single(_) => return Vec::new(),
+        Primitive(op, a, b) => (*op, Single(*a), Single(*b)),
+        Compound(op, l, r) => (*op, (**l).clone(), (**r).clone()),
+    };
+    let (lv, rv) = match (l.checked_eval(), r.checked_eval()) {
+        (Some(lv), Some(rv)) => (lv, rv),
+        _ => return Vec::new(),
+    };
+
+    let mut out = step(op, lv, rv);
+    out.extend(variants(&l, step).iter().filter_map(|v| op.checked_apply(v, &rv)));
+    out.extend(variants(&r, step).iter().filter_map(|v| op.checked_apply(&lv, v)));
+    out
+}
+
+/// value of a written expression read strictly left to right, brackets
+/// still first
+fn left_to_right(s: &str) -> Option<i32> {
+    fn seq(chars: &[char], pos: &mut usize) -> Option<i32> {
+        let mut v = atom(chars, pos)?;
+        while let Some(&c) = chars.get(*pos) {
+            let op = match c {
+                '+' => Op::Add,
+                '-' => Op::Minus,
+                'x' => Op::Mul,
+                '÷' => Op::Div,
+                _ => break,
+            };
+            *pos += 1;
+            v = op.checked_apply(&v, &atom(chars, pos)?)?;
+        }
+        Some(v)
+    }
+
+    fn atom(chars: &[char], pos: &mut usize) -> Option<i32> {
+        if chars.get(*pos) == Some(&'(') {
+            *pos += 1;
+            let v = seq(chars, pos)?;
+            *pos += 1;
+            return Some(v);
+        }
+        let start = *pos;
+        while matches!(chars.get(*pos), Some(c) if c.is_ascii_digit()) {
+            *pos += 1;
+        }
+        chars[start..*pos].iter().collect::<String>().parse().ok()
+    }
+
+    let chars = s.chars().collect::<Vec<_>>();
+    seq(&chars, &mut 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnose_mistakes() {
+        let b = Box::new;
+        assert_eq!(grade(&Primitive(Op::Add, 38, 45), 83), Grade::Correct);
+
+        let e = Compound(Op::Add, b(Single(2)), b(Primitive(Op::Mul, 3, 4)));
+        assert_eq!(grade(&e, 20), Grade::Incorrect(Some(Mistake::LeftToRight)));
+        assert_eq!(grade(&Primitive(Op::Add, 38, 45), 73), Grade::Incorrect(Some(Mistake::DroppedCarry)));
+        assert_eq!(grade(&Primitive(Op::Minus, 52, 17), 45), Grade::Incorrect(Some(Mistake::MissingBorrow)));
+        let e = Compound(Op::Add, b(Single(20)), b(Primitive(Op::Minus, 3, 5)));
+        assert_eq!(grade(&e, 22), Grade::Incorrect(Some(Mistake::SwappedSubtraction)));
+        let e = Compound(Op::Add, b(Primitive(Op::Mul, 7, 8)), b(Single(10)));
+        assert_eq!(grade(&e, 73), Grade::Incorrect(Some(Mistake::MultiplicationFact)));
+        assert_eq!(grade(&Primitive(Op::Div, 56, 7), 9), Grade::Incorrect(Some(Mistake::MultiplicationFact)));
+        assert_eq!(grade(&Primitive(Op::Add, 38, 45), 100), Grade::Incorrect(None));
+    }
+}
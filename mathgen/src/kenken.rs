@@ -0,0 +1,281 @@
+//! Cage puzzles in the style of KenKen (算独): fill an n x n grid with 1 to
+//! n so no number repeats in a row or column, and the numbers of each cage
+//! make its target with its operator.
+//!
+//! A puzzle is built backwards from a random Latin square: the grid is cut
+//! into cages, each cage gets an operator its numbers allow and the target
+//! they make, and a backtracking solver keeps only puzzles with a single
+//! solution. `-` and `÷` cages always have two cells and are read as the
+//! larger number against the smaller.
+
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+pub type Cell = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cage {
+    /// (row, column), the first one carries the label
+    pub cells: Vec<Cell>,
+    /// `None` for a single given cell
+    pub op: Option<Op>,
+    pub target: i32,
+}
+
+impl Cage {
+    /// whether `values`, one per cell, make the target
+    pub fn satisfied(&self, values: &[i32]) -> bool {
+        self.value(values) == Some(self.target)
+    }
+
+    fn value(&self, values: &[i32]) -> Option<i32> {
+        match self.op {
+            None => values.first().copied(),
+            Some(op @ Op::Add) | Some(op @ Op::Mul) => {
+                let (first, rest) = values.split_first()?;
+                rest.iter().try_fold(*first, |acc, v| op.checked_apply(&acc, v))
+            }
+            Some(op) => match values {
+                [a, b] if op == Op::Div && a.max(b) % a.min(b) != 0 => None,
+                [a, b] => op.checked_apply(a.max(b), a.min(b)),
+                _ => None,
+            },
+        }
+    }
+
+    /// whether `values` for some of the cells can still be completed, the
+    /// other cells taking values from 1 to `n`
+    fn possible(&self, values: &[i32], n: i32) -> bool {
+        let left = (self.cells.len() - values.len()) as i32;
+        if left == 0 {
+            return self.satisfied(values);
+        }
+        match self.op {
+            Some(Op::Add) => {
+                let sum: i32 = values.iter().sum();
+                sum + left <= self.target && sum + left * n >= self.target
+            }
+            Some(Op::Mul) => {
+                let product: i32 = values.iter().product();
+                self.target % product == 0
+            }
+            _ => true,
+        }
+    }
+
+    /// `12+`, `2÷`, or the given number
+    pub fn label(&self) -> String {
+        match self.op {
+            Some(op) => format!("{}{}", self.target, op),
+            None => self.target.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KenKen {
+    pub size: usize,
+    pub cages: Vec<Cage>,
+    /// the single solution, by row
+    pub solution: Vec<Vec<i32>>,
+}
+
+/// random Latin square of 1 to `n`: a cyclic square with its rows, columns
+/// and numbers shuffled
+pub fn latin_square<G: Rng>(n: usize, rng: &mut G) -> Vec<Vec<i32>> {
+    let mut rows = (0..n).collect::<Vec<_>>();
+    let mut cols = rows.clone();
+    let mut numbers = (1..=n as i32).collect::<Vec<_>>();
+    rows.shuffle(rng);
+    cols.shuffle(rng);
+    numbers.shuffle(rng);
+    rows.iter()
+        .map(|&r| cols.iter().map(|&c| numbers[(r + c) % n]).collect())
+        .collect()
+}
+
+/// the n x n grid cut into connected groups of 1 to `max` cells
+pub fn partition<G: Rng>(n: usize, max: usize, rng: &mut G) -> Vec<Vec<Cell>> {
+    let mut owner = vec![vec![None; n]; n];
+    let mut order = (0..n * n).map(|i| (i / n, i % n)).collect::<Vec<_>>();
+    order.shuffle(rng);
+
+    let mut groups: Vec<Vec<Cell>> = Vec::new();
+    for start in order {
+        if owner[start.0][start.1].is_some() {
+            continue;
+        }
+        let want = rng.gen_range(1, max.max(1) + 1);
+        let id = groups.len();
+        let mut group = vec![start];
+        owner[start.0][start.1] = Some(id);
+        while group.len() < want {
+            let free = group
+                .iter()
+                .flat_map(|&c| neighbours(c, n))
+                .filter(|&(r, c)| owner[r][c].is_none())
+                .collect::<Vec<_>>();
+            match free.choose(rng) {
+                Some(&(r, c)) => {
+                    owner[r][c] = Some(id);
+                    group.push((r, c));
+                }
+                None => break,
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+    groups
+}
+
+fn neighbours((r, c): Cell, n: usize) -> Vec<Cell> {
+    let mut out = Vec::new();
+    if r > 0 {
+        out.push((r - 1, c));
+    }
+    if r + 1 < n {
+        out.push((r + 1, c));
+    }
+    if c > 0 {
+        out.push((r, c - 1));
+    }
+    if c + 1 < n {
+        out.push((r, c + 1));
+    }
+    out
+}
+
+impl KenKen {
+    /// a puzzle on a random Latin square with cages of up to `max_cage`
+    /// cells and operators from `ops`, `None` when its solution is not
+    /// unique or a cage has no operator from `ops` that fits
+    pub fn random<G: Rng>(size: usize, max_cage: usize, ops: OpSet, rng: &mut G) -> Option<KenKen> {
+        let solution = latin_square(size, rng);
+        let mut cages = Vec::new();
+        for cells in partition(size, max_cage, rng) {
+            let values = cells.iter().map(|&(r, c)| solution[r][c]).collect::<Vec<_>>();
+            if cells.len() == 1 {
+                cages.push(Cage { cells, op: None, target: values[0] });
+                continue;
+            }
+
+            let fits = ops
+                .iter()
+                .filter_map(|op| {
+                    let cage = Cage { cells: cells.clone(), op: Some(op), target: 0 };
+                    let target = cage.value(&values)?;
+                    if op == Op::Minus && target == 0 {
+                        return None;
+                    }
+                    Some(Cage { target, ..cage })
+                })
+                .collect::<Vec<_>>();
+            cages.push(fits.choose(rng)?.clone());
+        }
+
+        let puzzle = KenKen { size, cages, solution };
+        if puzzle.solutions(2).len() == 1 {
+            Some(puzzle)
+        } else {
+            None
+        }
+    }
+
+    /// cage of every cell, by row
+    fn owners(&self) -> Vec<Vec<usize>> {
+        let mut owner = vec![vec![0; self.size]; self.size];
+        for (i, cage) in self.cages.iter().enumerate() {
+            for &(r, c) in &cage.cells {
+                owner[r][c] = i;
+            }
+        }
+        owner
+    }
+
+    /// up to `limit` solutions by backtracking, cell by cell in reading
+    /// order
+    pub fn solutions(&self, limit: usize) -> Vec<Vec<Vec<i32>>> {
+        let mut grid = vec![vec![0; self.size]; self.size];
+        let mut found = Vec::new();
+        self.search(0, &self.owners(), &mut grid, &mut found, limit);
+        found
+    }
+
+    fn search(&self, i: usize, owner: &[Vec<usize>], grid: &mut Vec<Vec<i32>>, found: &mut Vec<Vec<Vec<i32>>>, limit: usize) {
+        let n = self.size;
+        if found.len() >= limit {
+            return;
+        }
+        if i == n * n {
+            found.push(grid.clone());
+            return;
+        }
+
+        let (r, c) = (i / n, i % n);
+        let cage = &self.cages[owner[r][c]];
+        for v in 1..=n as i32 {
+            if (0..c).any(|k| grid[r][k] == v) || (0..r).any(|k| grid[k][c] == v) {
+                continue;
+            }
+            grid[r][c] = v;
+            // cells of the cage filled so far, this one included
+            let values = cage
+                .cells
+                .iter()
+                .filter(|&&(cr, cc)| cr * n + cc <= i)
+                .map(|&(cr, cc)| grid[cr][cc])
+                .collect::<Vec<_>>();
+            if cage.possible(&values, n as i32) {
+                self.search(i + 1, owner, grid, found, limit);
+            }
+        }
+        grid[r][c] = 0;
+    }
+}
+
+/// the cage labels over the grid, one row a line, `.` for cells without
+/// a label
+impl fmt::Display for KenKen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut labels = vec![vec![".".to_string(); self.size]; self.size];
+        for cage in &self.cages {
+            let (r, c) = cage.cells[0];
+            labels[r][c] = cage.label();
+        }
+        for row in labels {
+            writeln!(f, "{}", row.iter().map(|l| format!("{:>5}", l)).collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kenken_solutions() {
+        let cage = |cells: Vec<Cell>, op, target| Cage { cells, op, target };
+        let mut p = KenKen {
+            size: 2,
+            cages: vec![cage(vec![(0, 0), (0, 1)], Some(Op::Minus), 1), cage(vec![(1, 0), (1, 1)], Some(Op::Add), 3)],
+            solution: vec![vec![1, 2], vec![2, 1]],
+        };
+        assert_eq!(p.solutions(5).len(), 2);
+        assert_eq!(p.cages[0].label(), "1-");
+
+        p.cages = vec![
+            cage(vec![(0, 0)], None, 1),
+            cage(vec![(0, 1), (1, 1)], Some(Op::Div), 2),
+            cage(vec![(1, 0)], None, 2),
+        ];
+        assert_eq!(p.solutions(5), vec![p.solution.clone()]);
+
+        let mut rng = rand::thread_rng();
+        let p = (0..1000).find_map(|_| KenKen::random(4, 3, OpSet::ALL, &mut rng)).unwrap();
+        assert_eq!(p.solutions(2), vec![p.solution.clone()]);
+    }
+}
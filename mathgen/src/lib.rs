@@ -1,2 +1,26 @@
 pub mod math;
-
+pub mod vertical;
+pub mod division;
+pub mod bond;
+pub mod facts;
+pub mod shortcut;
+pub mod enumerate;
+pub mod weights;
+pub mod template;
+pub mod target;
+pub mod points;
+pub mod operators;
+pub mod brackets;
+pub mod grading;
+pub mod choice;
+pub mod sequence;
+pub mod ratio;
+pub mod percent;
+pub mod chinese;
+pub mod spoken;
+pub mod chain;
+pub mod anzan;
+pub mod kenken;
+pub mod pyramid;
+pub mod crossnumber;
+pub mod magic;
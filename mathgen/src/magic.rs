@@ -0,0 +1,292 @@
+//! Sum grids: magic squares (幻方) and grids with their row and column sums
+//! printed beside them.
+//!
+//! In a magic square every row, column and both diagonals add up to the
+//! same sum, and each number of the puzzle's set is used once. In a sum
+//! grid numbers may repeat and only the rows and columns are checked,
+//! against the sums given. Either way a few cells are filled in, and a
+//! small backtracking solver makes sure the rest can only go one way.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+pub type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridKind {
+    Magic,
+    Sums,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridPuzzle {
+    pub kind: GridKind,
+    /// the solution, by row
+    pub cells: Vec<Vec<i32>>,
+    /// cells printed on the sheet
+    pub given: Vec<Vec<bool>>,
+    /// what a blank can hold: the set to use once each for a magic
+    /// square, the range of values for a sum grid
+    pub values: Vec<i32>,
+}
+
+impl GridPuzzle {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// the rows, the columns, and for a magic square the two diagonals
+    pub fn lines(&self) -> Vec<Vec<Cell>> {
+        let n = self.size();
+        let mut lines = Vec::new();
+        for i in 0..n {
+            lines.push((0..n).map(|j| (i, j)).collect());
+        }
+        for j in 0..n {
+            lines.push((0..n).map(|i| (i, j)).collect());
+        }
+        if self.kind == GridKind::Magic {
+            lines.push((0..n).map(|i| (i, i)).collect());
+            lines.push((0..n).map(|i| (i, n - 1 - i)).collect());
+        }
+        lines
+    }
+
+    /// the sum of each line of `lines`, from the solution
+    pub fn sums(&self) -> Vec<i32> {
+        self.lines()
+            .iter()
+            .map(|line| line.iter().map(|&(r, c)| self.cells[r][c]).sum())
+            .collect()
+    }
+
+    pub fn givens(&self) -> usize {
+        self.given.iter().flatten().filter(|&&g| g).count()
+    }
+
+    /// up to `limit` ways to fill the blanks
+    pub fn solutions(&self, limit: usize) -> Vec<Vec<Vec<i32>>> {
+        let n = self.size();
+        let mut grid = (0..n)
+            .map(|r| (0..n).map(|c| if self.given[r][c] { Some(self.cells[r][c]) } else { None }).collect())
+            .collect::<Vec<Vec<_>>>();
+        let mut used = vec![false; self.values.len()];
+        if self.kind == GridKind::Magic {
+            for v in grid.iter().flatten().flatten() {
+                match self.values.iter().position(|x| x == v) {
+                    Some(i) if !used[i] => used[i] = true,
+                    _ => return Vec::new(),
+                }
+            }
+        }
+
+        let solver = Solver {
+            puzzle: self,
+            lines: self.lines(),
+            sums: self.sums(),
+            blanks: (0..n * n).map(|i| (i / n, i % n)).filter(|&(r, c)| !self.given[r][c]).collect(),
+        };
+        let mut found = Vec::new();
+        solver.search(0, &mut grid, &mut used, &mut found, limit);
+        found
+    }
+
+    /// whether the blanks can only be filled one way
+    pub fn unique(&self) -> bool {
+        self.solutions(2).len() == 1
+    }
+
+    /// a random magic square of `n` x `n` using `values`, which must hold
+    /// `n * n` numbers, all blank. `None` when the numbers make no magic
+    /// square.
+    pub fn magic<G: Rng>(n: usize, values: &[i32], rng: &mut G) -> Option<GridPuzzle> {
+        if values.len() != n * n || values.iter().sum::<i32>() % n as i32 != 0 {
+            return None;
+        }
+        let mut values = values.to_vec();
+        values.shuffle(rng);
+
+        // an empty square whose every line must make the magic sum, the
+        // shuffled values make the first square found a random one
+        let sum = values.iter().sum::<i32>() / n as i32;
+        let mut p = GridPuzzle {
+            kind: GridKind::Magic,
+            cells: vec![vec![0; n]; n],
+            given: vec![vec![false; n]; n],
+            values,
+        };
+        let solver = Solver {
+            puzzle: &p,
+            lines: p.lines(),
+            sums: vec![sum; p.lines().len()],
+            blanks: (0..n * n).map(|i| (i / n, i % n)).collect(),
+        };
+        let mut found = Vec::new();
+        solver.search(0, &mut vec![vec![None; n]; n], &mut vec![false; p.values.len()], &mut found, 1);
+
+        p.cells = found.pop()?;
+        p.values.sort();
+        Some(p)
+    }
+
+    /// a random `n` x `n` sum grid of numbers from `values`, all blank
+    pub fn sum_grid<G: Rng>(n: usize, values: &[i32], rng: &mut G) -> Option<GridPuzzle> {
+        let cells = (0..n)
+            .map(|_| (0..n).map(|_| values.choose(rng).copied()).collect::<Option<Vec<_>>>())
+            .collect::<Option<Vec<_>>>()?;
+        Some(GridPuzzle {
+            kind: GridKind::Sums,
+            cells,
+            given: vec![vec![false; n]; n],
+            values: values.to_vec(),
+        })
+    }
+
+    /// fill cells in, in random order, until the solution is unique, then
+    /// hide those that are not needed, so that `givens` cells are left.
+    /// `None` when the solution needs more of them.
+    pub fn reveal<G: Rng>(mut self, givens: usize, rng: &mut G) -> Option<GridPuzzle> {
+        let n = self.size();
+        let mut cells = (0..n * n).map(|i| (i / n, i % n)).collect::<Vec<_>>();
+        cells.shuffle(rng);
+        for &(r, c) in &cells {
+            if self.givens() >= givens && self.unique() {
+                break;
+            }
+            self.given[r][c] = true;
+        }
+
+        cells.shuffle(rng);
+        for &(r, c) in &cells {
+            if self.givens() <= givens {
+                break;
+            }
+            if self.given[r][c] {
+                self.given[r][c] = false;
+                if !self.unique() {
+                    self.given[r][c] = true;
+                }
+            }
+        }
+
+        if self.givens() == givens && self.unique() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+struct Solver<'a> {
+    puzzle: &'a GridPuzzle,
+    lines: Vec<Vec<Cell>>,
+    sums: Vec<i32>,
+    blanks: Vec<Cell>,
+}
+
+impl<'a> Solver<'a> {
+    /// whether every line through `cell` can still make its sum with the
+    /// values left
+    fn possible(&self, cell: Cell, grid: &[Vec<Option<i32>>], used: &[bool]) -> bool {
+        let left = self
+            .puzzle
+            .values
+            .iter()
+            .zip(used)
+            .filter(|(_, &u)| !u || self.puzzle.kind == GridKind::Sums)
+            .map(|(&v, _)| v);
+        let (low, high) = left.fold((i32::MAX, i32::MIN), |(l, h), v| (l.min(v), h.max(v)));
+
+        self.lines.iter().zip(&self.sums).filter(|(line, _)| line.contains(&cell)).all(|(line, &sum)| {
+            let (mut total, mut empty) = (0, 0);
+            for &(r, c) in line {
+                match grid[r][c] {
+                    Some(v) => total += v,
+                    None => empty += 1,
+                }
+            }
+            match empty {
+                0 => total == sum,
+                _ => total + low * empty <= sum && total + high * empty >= sum,
+            }
+        })
+    }
+
+    fn search(&self, i: usize, grid: &mut Vec<Vec<Option<i32>>>, used: &mut Vec<bool>, found: &mut Vec<Vec<Vec<i32>>>, limit: usize) {
+        if found.len() >= limit {
+            return;
+        }
+        let (r, c) = match self.blanks.get(i) {
+            Some(&cell) => cell,
+            None => {
+                found.push(grid.iter().map(|row| row.iter().map(|v| v.unwrap_or(0)).collect()).collect());
+                return;
+            }
+        };
+
+        let once = self.puzzle.kind == GridKind::Magic;
+        for (k, &v) in self.puzzle.values.iter().enumerate() {
+            if once && used[k] {
+                continue;
+            }
+            grid[r][c] = Some(v);
+            used[k] = true;
+            if self.possible((r, c), grid, used) {
+                self.search(i + 1, grid, used, found, limit);
+            }
+            used[k] = false;
+        }
+        grid[r][c] = None;
+    }
+}
+
+/// the grid by row, `__` for blanks, a sum grid with its row sums after
+/// each row and its column sums below
+impl fmt::Display for GridPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.size();
+        let sums = self.sums();
+        for (r, sum) in sums.iter().enumerate().take(n) {
+            for c in 0..n {
+                match self.given[r][c] {
+                    true => write!(f, "{:>4}", self.cells[r][c])?,
+                    false => write!(f, "{:>4}", "__")?,
+                }
+            }
+            if self.kind == GridKind::Sums {
+                write!(f, " |{:>4}", sum)?;
+            }
+            writeln!(f)?;
+        }
+        if self.kind == GridKind::Sums {
+            writeln!(f, "{}", sums[n..].iter().map(|s| format!("{:>4}", s)).collect::<String>())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sum_grids() {
+        let mut rng = rand::thread_rng();
+        let values = (1..10).collect::<Vec<_>>();
+        let p = GridPuzzle::magic(3, &values, &mut rng).unwrap();
+        assert!(p.sums().iter().all(|&s| s == 15), "{}", p);
+        // a magic square of 1 to 9 comes in eight reflections and turns
+        assert_eq!(p.solutions(10).len(), 8);
+        let p = p.reveal(2, &mut rng).unwrap();
+        assert_eq!(p.solutions(2), vec![p.cells.clone()]);
+
+        let values = (0..16).map(|k| 5 + 2 * k).collect::<Vec<_>>();
+        let p = GridPuzzle::magic(4, &values, &mut rng).unwrap();
+        assert!(p.sums().iter().all(|&s| s == 4 * 5 + 2 * 30), "{}", p);
+
+        let p = GridPuzzle::sum_grid(3, &[1, 2, 3, 4, 5], &mut rng).unwrap();
+        let p = (0..100).find_map(|_| p.clone().reveal(5, &mut rng)).unwrap();
+        assert!(p.unique(), "{}", p);
+    }
+}
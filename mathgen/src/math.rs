@@ -1,5 +1,6 @@
 use std::fmt::*;
 
+pub use num_bigint::BigInt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Op {
@@ -20,15 +21,116 @@ impl Display for Op {
     }
 }
 
+impl Op {
+    pub const ALL: [Op; 4] = [Op::Add, Op::Minus, Op::Mul, Op::Div];
+
+    /// `a op b`, `None` on overflow or division by zero
+    pub fn checked_apply<N: Number>(self, a: &N, b: &N) -> Option<N> {
+        N::checked_apply(self, a, b)
+    }
+}
+
+/// set of operators, e.g. the operators an expression uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OpSet(u8);
+
+impl OpSet {
+    pub const ALL: OpSet = OpSet(0b1111);
+
+    pub fn empty() -> OpSet {
+        OpSet(0)
+    }
+
+    fn bit(op: Op) -> u8 {
+        match op {
+            Op::Add => 1,
+            Op::Minus => 2,
+            Op::Mul => 4,
+            Op::Div => 8,
+        }
+    }
+
+    pub fn with(self, op: Op) -> OpSet {
+        OpSet(self.0 | Self::bit(op))
+    }
+
+    pub fn union(self, other: OpSet) -> OpSet {
+        OpSet(self.0 | other.0)
+    }
+
+    pub fn contains(self, op: Op) -> bool {
+        self.0 & Self::bit(op) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Op> {
+        Op::ALL.iter().cloned().filter(move |op| self.contains(*op))
+    }
+}
+
+impl std::iter::FromIterator<Op> for OpSet {
+    fn from_iter<I: IntoIterator<Item = Op>>(iter: I) -> Self {
+        iter.into_iter().fold(OpSet::empty(), OpSet::with)
+    }
+}
+
+/// numeric type an `Expr` is built from
+pub trait Number: Clone + Debug + Display + PartialEq + PartialOrd {
+    fn from_i32(v: i32) -> Self;
+    /// `a op b`, `None` on overflow or division by zero. Integer types
+    /// truncate the quotient.
+    fn checked_apply(op: Op, a: &Self, b: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => ($(
+        impl Number for $t {
+            fn from_i32(v: i32) -> Self {
+                v as $t
+            }
+
+            fn checked_apply(op: Op, a: &Self, b: &Self) -> Option<Self> {
+                match op {
+                    Op::Add => a.checked_add(*b),
+                    Op::Minus => a.checked_sub(*b),
+                    Op::Mul => a.checked_mul(*b),
+                    Op::Div => a.checked_div(*b),
+                }
+            }
+        }
+    )*)
+}
+
+impl_number!(i32, i64);
+
+impl Number for BigInt {
+    fn from_i32(v: i32) -> Self {
+        BigInt::from(v)
+    }
+
+    fn checked_apply(op: Op, a: &Self, b: &Self) -> Option<Self> {
+        match op {
+            Op::Add => Some(a + b),
+            Op::Minus => Some(a - b),
+            Op::Mul => Some(a * b),
+            Op::Div if *b == BigInt::from(0) => None,
+            Op::Div => Some(a / b),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub enum Expr {
-    Single(i32),
-    Primitive(Op, i32, i32),
-    Compound(Op, Box<Expr>, Box<Expr>),
+pub enum Expr<N: Number = i32> {
+    Single(N),
+    Primitive(Op, N, N),
+    Compound(Op, Box<Expr<N>>, Box<Expr<N>>),
 }
 
 /// render Expr with least brackets required
-impl Display for Expr {
+impl<N: Number> Display for Expr<N> {
     fn fmt(&self, f: &mut Formatter) -> Result {
         match self {
             Single(v) => write!(f, "{}", v),
@@ -64,7 +166,7 @@ impl Display for Expr {
                     match v2.as_ref() {
                         Single(_) => write!(f, "{}", v2),
                         Primitive(op2, _, _) | Compound(op2, _, _) => {
-                            if *op2 == Op::Add || *op2 == Op::Minus || *op2 == Op::Div {
+                            if *op2 == Op::Add || *op2 == Op::Minus || *op2 == Op::Div || *op == Op::Div {
                                 write!(f, "({})", v2)
                             } else {
                                 write!(f, "{}", v2)
@@ -79,32 +181,54 @@ impl Display for Expr {
 
 use self::Expr::*;
 
-impl Expr {
-    pub fn eval(&self) -> i32 {
+impl<N: Number> Expr<N> {
+    /// panics on overflow or division by zero, see `checked_eval`
+    pub fn eval(&self) -> N {
+        self.checked_eval().expect("expression overflows or divides by zero")
+    }
+
+    pub fn checked_eval(&self) -> Option<N> {
         match self {
-            Single(v) => *v,
-            Primitive(op, v1, v2) => match op {
-                Op::Div => *v1 / *v2,
-                Op::Mul => *v1 * *v2,
-                Op::Minus => *v1 - *v2,
-                _ => *v1 + *v2,
-            },
-            Compound(op, v1, v2) => match op {
-                Op::Div => v1.eval() / v2.eval(),
-                Op::Mul => v1.eval() * v2.eval(),
-                Op::Minus => v1.eval() - v2.eval(),
-                _ => v1.eval() + v2.eval(),
-            },
+            Single(v) => Some(v.clone()),
+            Primitive(op, v1, v2) => op.checked_apply(v1, v2),
+            Compound(op, v1, v2) => op.checked_apply(&v1.checked_eval()?, &v2.checked_eval()?),
         }
     }
 
-    pub fn validate<V: Validator>(&self, validator: &mut V) -> bool {
+    /// `l op r`, as a `Primitive` when both sides are plain numbers
+    pub fn join(op: Op, l: Expr<N>, r: Expr<N>) -> Expr<N> {
+        match (l, r) {
+            (Single(a), Single(b)) => Primitive(op, a, b),
+            (l, r) => Compound(op, Box::new(l), Box::new(r)),
+        }
+    }
+
+    /// operators used anywhere in the tree
+    pub fn ops(&self) -> OpSet {
+        match self {
+            Single(_) => OpSet::empty(),
+            Primitive(op, _, _) => OpSet::empty().with(*op),
+            Compound(op, v1, v2) => v1.ops().union(v2.ops()).with(*op),
+        }
+    }
+
+    /// same tree with every number converted by `f`, e.g. to widen an
+    /// `Expr<i32>` into an `Expr<BigInt>`
+    pub fn map<M: Number, F: Fn(&N) -> M>(&self, f: &F) -> Expr<M> {
         match self {
-            Single(v) => validator.on_single(*v),
+            Single(v) => Single(f(v)),
+            Primitive(op, v1, v2) => Primitive(*op, f(v1), f(v2)),
+            Compound(op, v1, v2) => Compound(*op, Box::new(v1.map(f)), Box::new(v2.map(f))),
+        }
+    }
+
+    pub fn validate<V: Validator<N>>(&self, validator: &mut V) -> bool {
+        match self {
+            Single(v) => validator.on_single(v.clone()),
             Primitive(op, v1, v2) => {
-                validator.on_single(*v1)
-                    && validator.on_single(*v2)
-                    && validator.on_primitive(*op, *v1, *v2)
+                validator.on_single(v1.clone())
+                    && validator.on_single(v2.clone())
+                    && validator.on_primitive(*op, v1.clone(), v2.clone())
             }
             Compound(op, v1, v2) => {
                 v1.validate(validator)
@@ -115,9 +239,9 @@ impl Expr {
     }
 }
 
-pub trait Validator {
-    fn on_single(&mut self, v: i32) -> bool;
-    fn on_primitive(&mut self, op: Op, v1: i32, v2: i32) -> bool;
+pub trait Validator<N: Number = i32> {
+    fn on_single(&mut self, v: N) -> bool;
+    fn on_primitive(&mut self, op: Op, v1: N, v2: N) -> bool;
     fn pass(&self) -> bool;
     fn init(&mut self);
 }
@@ -127,3 +251,23 @@ pub trait MathGenerator {
     fn gen(&mut self, noprand: i32, nop: i32) -> Expr;
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_backends() {
+        let e = Compound(Op::Mul, Box::new(Primitive(Op::Add, 2, 3)), Box::new(Single(4)));
+        assert_eq!(e.eval(), 20);
+        assert_eq!(Primitive(Op::Div, 7, 0).checked_eval(), None);
+
+        let big: Expr<i32> = Compound(Op::Mul, Box::new(Single(100_000)), Box::new(Single(100_000)));
+        assert_eq!(big.checked_eval(), None);
+        assert_eq!(big.map(&|v| *v as i64).eval(), 10_000_000_000);
+
+        let huge = big.map(&|v| BigInt::from(*v));
+        let huge = Compound(Op::Mul, Box::new(huge.clone()), Box::new(huge));
+        assert_eq!(huge.eval().to_string(), "100000000000000000000");
+        assert_eq!(huge.to_string(), "100000x100000x100000x100000");
+    }
+}
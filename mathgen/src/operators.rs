@@ -0,0 +1,204 @@
+//! Fill-in-the-operator puzzles (填运算符): `3○4○5=17`.
+//!
+//! The numbers keep their order, students pick an operator for every
+//! circle and, when `brackets` is set, may also group them. Every step has
+//! to stay a whole number.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorPuzzle {
+    pub numbers: Vec<i32>,
+    pub result: i32,
+    /// operators allowed in the circles
+    pub ops: OpSet,
+    pub brackets: bool,
+}
+
+/// numbers of `e` from left to right
+pub fn leaves(e: &Expr) -> Vec<i32> {
+    match e {
+        Single(v) => vec![*v],
+        Primitive(_, a, b) => vec![*a, *b],
+        Compound(_, l, r) => {
+            let mut out = leaves(l);
+            out.extend(leaves(r));
+            out
+        }
+    }
+}
+
+/// whether `l op r` stays a whole number, neither negative nor a fraction
+fn exact(op: Op, l: i32, r: i32) -> bool {
+    match op {
+        Op::Minus => l >= r,
+        Op::Div => r != 0 && l % r == 0,
+        _ => true,
+    }
+}
+
+/// value of `e` if every step is a whole number
+pub fn whole(e: &Expr) -> Option<i32> {
+    let (op, l, r) = match e {
+        Single(v) => return Some(*v),
+        Primitive(op, a, b) => (*op, *a, *b),
+        Compound(op, l, r) => (*op, whole(l)?, whole(r)?),
+    };
+    if exact(op, l, r) {
+        op.checked_apply(&l, &r)
+    } else {
+        None
+    }
+}
+
+/// `numbers` joined by `ops` without brackets, `x ÷` before `+ -`
+pub fn chain(numbers: &[i32], ops: &[Op]) -> Expr {
+    // the sum so far with the operator waiting for the next term
+    let mut sum: Option<(Expr, Op)> = None;
+    let mut term = Single(numbers[0]);
+    for (&op, &v) in ops.iter().zip(numbers[1..].iter()) {
+        if op == Op::Mul || op == Op::Div {
+            term = Expr::join(op, term, Single(v));
+            continue;
+        }
+        let done = match sum.take() {
+            Some((s, p)) => Expr::join(p, s, term),
+            None => term,
+        };
+        sum = Some((done, op));
+        term = Single(v);
+    }
+    match sum {
+        Some((s, p)) => Expr::join(p, s, term),
+        None => term,
+    }
+}
+
+/// every bracketing of `numbers` in order, with operators from `ops`
+fn trees(numbers: &[i32], ops: OpSet) -> Vec<(Expr, i32)> {
+    if numbers.len() == 1 {
+        return vec![(Single(numbers[0]), numbers[0])];
+    }
+
+    let mut out = Vec::new();
+    for i in 1..numbers.len() {
+        let (ls, rs) = (trees(&numbers[..i], ops), trees(&numbers[i..], ops));
+        for (le, lv) in &ls {
+            for (re, rv) in &rs {
+                for op in ops.iter() {
+                    if let Some(v) = whole(&Primitive(op, *lv, *rv)) {
+                        out.push((Expr::join(op, le.clone(), re.clone()), v));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// every bracketing of `numbers` with `ops` between them, in order
+pub fn groupings(numbers: &[i32], ops: &[Op]) -> Vec<(Expr, i32)> {
+    if numbers.len() == 1 {
+        return vec![(Single(numbers[0]), numbers[0])];
+    }
+
+    let mut out = Vec::new();
+    for i in 1..numbers.len() {
+        let ls = groupings(&numbers[..i], &ops[..i - 1]);
+        let rs = groupings(&numbers[i..], &ops[i..]);
+        for (le, lv) in &ls {
+            for (re, rv) in &rs {
+                if let Some(v) = whole(&Primitive(ops[i - 1], *lv, *rv)) {
+                    out.push((Expr::join(ops[i - 1], le.clone(), re.clone()), v));
+                }
+            }
+        }
+    }
+    out
+}
+
+impl OperatorPuzzle {
+    /// the puzzle `e` is an answer to
+    pub fn from_expr(e: &Expr, ops: OpSet, brackets: bool) -> OperatorPuzzle {
+        OperatorPuzzle {
+            numbers: leaves(e),
+            result: e.eval(),
+            ops,
+            brackets,
+        }
+    }
+
+    /// every answer, as written: two groupings that read the same are one
+    /// answer
+    pub fn solutions(&self) -> Vec<Expr> {
+        if self.numbers.is_empty() {
+            return Vec::new();
+        }
+        let found = if self.brackets {
+            trees(&self.numbers, self.ops)
+                .into_iter()
+                .filter(|(_, v)| *v == self.result)
+                .map(|(e, _)| e)
+                .collect::<Vec<_>>()
+        } else {
+            let choices = self.ops.iter().collect::<Vec<_>>();
+            let slots = self.numbers.len() - 1;
+            let mut found = Vec::new();
+            for n in 0..choices.len().pow(slots as u32) {
+                let seq = (0..slots)
+                    .map(|i| choices[n / choices.len().pow(i as u32) % choices.len()])
+                    .collect::<Vec<_>>();
+                let e = chain(&self.numbers, &seq);
+                if whole(&e) == Some(self.result) {
+                    found.push(e);
+                }
+            }
+            found
+        };
+
+        let mut seen = HashSet::new();
+        found.into_iter().filter(|e| seen.insert(e.to_string())).collect()
+    }
+}
+
+/// `3○4○5=17`
+impl fmt::Display for OperatorPuzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let numbers = self.numbers.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        write!(f, "{}={}", numbers.join("○"), self.result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operator_puzzles() {
+        let e = chain(&[3, 4, 5, 2], &[Op::Add, Op::Mul, Op::Minus]);
+        assert_eq!(e.to_string(), "3+4x5-2");
+        assert_eq!(e.eval(), 21);
+        assert_eq!(leaves(&e), vec![3, 4, 5, 2]);
+
+        let p = OperatorPuzzle::from_expr(&e, OpSet::ALL, false);
+        assert_eq!(p.to_string(), "3○4○5○2=21");
+        assert!(p.solutions().iter().any(|s| s.to_string() == "3+4x5-2"));
+        assert!(p.solutions().iter().all(|s| s.eval() == 21));
+
+        // (3+4)x5 needs brackets
+        let p = OperatorPuzzle {
+            numbers: vec![3, 4, 5],
+            result: 35,
+            ops: OpSet::ALL,
+            brackets: false,
+        };
+        assert!(p.solutions().is_empty());
+        let p = OperatorPuzzle { brackets: true, ..p };
+        let found = p.solutions();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].to_string(), "(3+4)x5");
+    }
+}
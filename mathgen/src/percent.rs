@@ -0,0 +1,110 @@
+//! Percent and ratio problems (百分数, 比), worked exactly with `Ratio`.
+
+use crate::chinese::to_chinese;
+use crate::math::Expr::*;
+use crate::math::*;
+use crate::ratio::Ratio;
+use std::fmt;
+
+/// percents as read in 百分之…
+fn chinese(n: i32) -> String {
+    to_chinese(i64::from(n)).expect("percents are small")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentProblem {
+    /// `percent`% of `whole`
+    PercentOf { whole: i32, percent: i32 },
+    /// `from` raised or lowered by `percent`%
+    Change { from: i32, percent: i32, increase: bool },
+    /// what percent `part` is of `whole`
+    WhatPercent { part: i32, whole: i32 },
+    /// `total` split in the ratio `a:b`
+    Split { total: i32, ratio: (i32, i32) },
+}
+
+impl PercentProblem {
+    /// the working behind each answer, `Split` has two
+    pub fn exprs(&self) -> Vec<Expr<Ratio>> {
+        let b = Box::new;
+        let exprs = match *self {
+            PercentProblem::PercentOf { whole, percent } => {
+                vec![Compound(Op::Mul, b(Single(whole)), b(Primitive(Op::Div, percent, 100)))]
+            }
+            PercentProblem::Change { from, percent, increase } => {
+                let op = if increase { Op::Add } else { Op::Minus };
+                let factor = Primitive(op, 100, percent);
+                vec![Compound(Op::Div, b(Compound(Op::Mul, b(Single(from)), b(factor))), b(Single(100)))]
+            }
+            PercentProblem::WhatPercent { part, whole } => {
+                vec![Compound(Op::Mul, b(Primitive(Op::Div, part, whole)), b(Single(100)))]
+            }
+            PercentProblem::Split { total, ratio: (l, r) } => [l, r]
+                .iter()
+                .map(|&share| {
+                    let each = Compound(Op::Div, b(Single(total)), b(Primitive(Op::Add, l, r)));
+                    Compound(Op::Mul, b(each), b(Single(share)))
+                })
+                .collect(),
+        };
+        exprs.iter().map(|e| e.map(&|v| Ratio::from_i32(*v))).collect()
+    }
+
+    pub fn answers(&self) -> Option<Vec<Ratio>> {
+        self.exprs().iter().map(|e| e.checked_eval()).collect()
+    }
+
+    /// answers with at most `places` decimals, `None` if one needs more
+    pub fn clean(&self, places: u32) -> Option<Vec<String>> {
+        self.answers()?.iter().map(|a| a.decimal(places)).collect()
+    }
+
+    /// answer key line: `84`, `25%` or `45, 75`
+    pub fn answer_text(&self, places: u32) -> Option<String> {
+        let answers = self.clean(places)?;
+        Some(match self {
+            PercentProblem::WhatPercent { .. } => format!("{}%", answers[0]),
+            _ => answers.join(", "),
+        })
+    }
+}
+
+/// `240的百分之三十五是多少?`
+impl fmt::Display for PercentProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PercentProblem::PercentOf { whole, percent } => {
+                write!(f, "{}的百分之{}是多少?", whole, chinese(percent))
+            }
+            PercentProblem::Change { from, percent, increase } => {
+                let verb = if increase { "增加" } else { "减少" };
+                write!(f, "{}{}百分之{}后是多少?", from, verb, chinese(percent))
+            }
+            PercentProblem::WhatPercent { part, whole } => write!(f, "{}是{}的百分之几?", part, whole),
+            PercentProblem::Split { total, ratio: (l, r) } => {
+                write!(f, "把{}按{}比{}分成两份, 每份各是多少?", total, l, r)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_problems() {
+        let p = PercentProblem::PercentOf { whole: 240, percent: 35 };
+        assert_eq!(p.to_string(), "240的百分之三十五是多少?");
+        assert_eq!(p.answer_text(0), Some("84".to_string()));
+
+        let p = PercentProblem::Change { from: 90, percent: 15, increase: false };
+        assert_eq!(p.answer_text(0), None);
+        assert_eq!(p.answer_text(1), Some("76.5".to_string()));
+
+        assert_eq!(PercentProblem::WhatPercent { part: 30, whole: 120 }.answer_text(0), Some("25%".to_string()));
+        let p = PercentProblem::Split { total: 120, ratio: (3, 5) };
+        assert_eq!(p.to_string(), "把120按3比5分成两份, 每份各是多少?");
+        assert_eq!(p.answer_text(0), Some("45, 75".to_string()));
+    }
+}
@@ -0,0 +1,194 @@
+//! The 24 game (算24点): combine four cards with `+ - x ÷` to make 24.
+//!
+//! Steps are worked out exactly, so a solution may pass through a fraction
+//! as in 8÷(3-8÷3), but never through a negative number. Two solutions are
+//! the same when they only differ by order or grouping, `-` and `÷`
+//! included: `(8-4)x6` and `6x(8-4)` are one solution, so are `8-(4-2)` and
+//! `8-4+2`.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use crate::ratio::Ratio;
+use crate::target::splits;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// expression up to commutativity and associativity: a chain of `+ -` or
+/// `x ÷` becomes the sorted terms on either side
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Canon {
+    Num(i32),
+    Sum(Vec<Canon>, Vec<Canon>),
+    Product(Vec<Canon>, Vec<Canon>),
+}
+
+fn is_additive(op: Op) -> bool {
+    op == Op::Add || op == Op::Minus
+}
+
+fn parts(e: &Expr) -> Option<(Op, Expr, Expr)> {
+    match e {
+        Single(_) => None,
+        Primitive(op, a, b) => Some((*op, Single(*a), Single(*b))),
+        Compound(op, l, r) => Some((*op, (**l).clone(), (**r).clone())),
+    }
+}
+
+/// terms of the chain `e` belongs to, `inverse` ones are subtracted or
+/// divided by
+fn gather(e: &Expr, additive: bool, inverse: bool, pos: &mut Vec<Canon>, neg: &mut Vec<Canon>) {
+    match parts(e) {
+        Some((op, l, r)) if is_additive(op) == additive => {
+            let flip = op == Op::Minus || op == Op::Div;
+            gather(&l, additive, inverse, pos, neg);
+            gather(&r, additive, inverse ^ flip, pos, neg);
+        }
+        _ if inverse => neg.push(Canon::of(e)),
+        _ => pos.push(Canon::of(e)),
+    }
+}
+
+impl Canon {
+    pub fn of(e: &Expr) -> Canon {
+        let additive = match e {
+            Single(v) => return Canon::Num(*v),
+            Primitive(op, ..) | Compound(op, ..) => is_additive(*op),
+        };
+
+        let (mut pos, mut neg) = (Vec::new(), Vec::new());
+        gather(e, additive, false, &mut pos, &mut neg);
+        pos.sort();
+        neg.sort();
+        if additive {
+            Canon::Sum(pos, neg)
+        } else {
+            Canon::Product(pos, neg)
+        }
+    }
+}
+
+pub fn equivalent(a: &Expr, b: &Expr) -> bool {
+    Canon::of(a) == Canon::of(b)
+}
+
+type Built = Vec<(Expr, Ratio)>;
+
+/// every tree over the sorted multiset `cards` with its exact value
+fn build(cards: &[i32], memo: &mut HashMap<Vec<i32>, Built>) -> Built {
+    if let Some(built) = memo.get(cards) {
+        return built.clone();
+    }
+
+    let mut out = Vec::new();
+    if cards.len() == 1 {
+        out.push((Single(cards[0]), Ratio::from_int(i64::from(cards[0]))));
+    }
+    for n in 1..cards.len() {
+        let mut seen = HashSet::new();
+        for (left, right) in splits(cards, n) {
+            if !seen.insert(left.clone()) {
+                continue;
+            }
+            let (ls, rs) = (build(&left, memo), build(&right, memo));
+            for (le, lv) in &ls {
+                for (re, rv) in &rs {
+                    for &op in Op::ALL.iter() {
+                        match op.checked_apply(lv, rv) {
+                            Some(v) if v >= Ratio::from_int(0) => {
+                                out.push((Expr::join(op, le.clone(), re.clone()), v))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    memo.insert(cards.to_vec(), out.clone());
+    out
+}
+
+/// every expression using each of `cards` once that comes out at `target`
+fn reaching(cards: &[i32], target: i32) -> impl Iterator<Item = Expr> {
+    let mut sorted = cards.to_vec();
+    sorted.sort();
+    let target = Ratio::from_int(i64::from(target));
+    build(&sorted, &mut HashMap::new())
+        .into_iter()
+        .filter(move |(_, v)| *v == target)
+        .map(|(e, _)| e)
+}
+
+/// every distinct way to make `target` from `cards`, each used once
+pub fn solve(cards: &[i32], target: i32) -> Vec<Expr> {
+    let mut seen = BTreeSet::new();
+    reaching(cards, target).filter(|e| seen.insert(Canon::of(e))).collect()
+}
+
+/// cards dealt for one round
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hand {
+    pub cards: Vec<i32>,
+    pub target: i32,
+}
+
+impl Hand {
+    pub fn new(cards: Vec<i32>) -> Hand {
+        Hand { cards, target: 24 }
+    }
+
+    pub fn solutions(&self) -> Vec<Expr> {
+        solve(&self.cards, self.target)
+    }
+
+    /// faster than counting the solutions
+    pub fn solvable(&self) -> bool {
+        reaching(&self.cards, self.target).next().is_some()
+    }
+}
+
+/// `3 4 5 6 → 24`
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.cards {
+            write!(f, "{} ", c)?;
+        }
+        write!(f, "→ {}", self.target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_24() {
+        let b = Box::new;
+        let a = Compound(Op::Mul, b(Primitive(Op::Minus, 8, 4)), b(Single(6)));
+        let c = Compound(Op::Mul, b(Single(6)), b(Primitive(Op::Minus, 8, 4)));
+        assert!(equivalent(&a, &c));
+        // 8-(4-2) is 8-4+2
+        let d = Compound(Op::Minus, b(Single(8)), b(Primitive(Op::Minus, 4, 2)));
+        let e = Compound(Op::Add, b(Primitive(Op::Minus, 8, 4)), b(Single(2)));
+        assert!(equivalent(&d, &e));
+        assert!(!equivalent(&d, &Compound(Op::Minus, b(Primitive(Op::Minus, 8, 4)), b(Single(2)))));
+
+        // hands that need a fraction on the way
+        for (cards, only) in [
+            ([3, 3, 8, 8], "8÷(3-8÷3)"),
+            ([1, 5, 5, 5], "5x(5-1÷5)"),
+            ([3, 7, 3, 7], "7x(3+3÷7)"),
+        ] {
+            let all = solve(&cards, 24);
+            assert_eq!(all.iter().map(|e| e.to_string()).collect::<Vec<_>>(), vec![only]);
+            assert!(Hand::new(cards.to_vec()).solvable());
+        }
+        assert!(!Hand::new(vec![1, 1, 1, 1]).solvable());
+        // a classic hard hand
+        let all = solve(&[4, 4, 10, 10], 24);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].to_string(), "(10x10-4)÷4");
+        assert!(solve(&[1, 2, 3, 4], 24).len() > 1);
+    }
+}
@@ -0,0 +1,209 @@
+//! Number pyramids (数塔): every brick is the sum, or the product, of the
+//! two bricks it stands on.
+//!
+//! Some bricks are hidden. A puzzle only stands when the hidden bricks can
+//! be worked out one at a time by plain arithmetic: whenever two bricks of
+//! a triangle are known the third follows, by adding, subtracting,
+//! multiplying or dividing. No guessing and no equations are needed.
+
+use crate::enumerate::Rules;
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pyramid {
+    /// `Add` or `Mul`
+    pub op: Op,
+    /// rows from the base up, each one brick shorter than the one below
+    pub rows: Vec<Vec<i32>>,
+    /// same shape as `rows`
+    pub hidden: Vec<Vec<bool>>,
+}
+
+/// every row above `base`, built with `op`
+pub fn build(base: &[i32], op: Op) -> Option<Vec<Vec<i32>>> {
+    let mut rows = vec![base.to_vec()];
+    while rows.last()?.len() > 1 {
+        let below = rows.last()?;
+        let row = below
+            .windows(2)
+            .map(|w| op.checked_apply(&w[0], &w[1]))
+            .collect::<Option<Vec<_>>>()?;
+        rows.push(row);
+    }
+    Some(rows)
+}
+
+/// third brick of a triangle from the other two, `l op r = top`
+fn deduce(op: Op, l: Option<i32>, r: Option<i32>, top: Option<i32>) -> [Option<i32>; 3] {
+    let inverse = |known: i32, whole: i32| match op {
+        Op::Mul if known == 0 || whole % known != 0 => None,
+        Op::Mul => Some(whole / known),
+        _ => whole.checked_sub(known),
+    };
+    match (l, r, top) {
+        (Some(a), Some(b), None) => [l, r, op.checked_apply(&a, &b)],
+        (Some(a), None, Some(c)) => [l, inverse(a, c), top],
+        (None, Some(b), Some(c)) => [inverse(b, c), r, top],
+        _ => [l, r, top],
+    }
+}
+
+impl Pyramid {
+    /// the visible bricks, `None` where hidden
+    pub fn shown(&self) -> Vec<Vec<Option<i32>>> {
+        self.rows
+            .iter()
+            .zip(&self.hidden)
+            .map(|(row, hidden)| row.iter().zip(hidden).map(|(&v, &h)| if h { None } else { Some(v) }).collect())
+            .collect()
+    }
+
+    /// the bricks worked out from the visible ones by arithmetic alone,
+    /// `None` when some stay unknown or the numbers do not fit together
+    pub fn solve(&self) -> Option<Vec<Vec<i32>>> {
+        let mut grid = self.shown();
+        loop {
+            let mut progress = false;
+            for k in 1..grid.len() {
+                for i in 0..grid[k].len() {
+                    let before = [grid[k - 1][i], grid[k - 1][i + 1], grid[k][i]];
+                    let after = deduce(self.op, before[0], before[1], before[2]);
+                    if after != before {
+                        progress = true;
+                        grid[k - 1][i] = after[0];
+                        grid[k - 1][i + 1] = after[1];
+                        grid[k][i] = after[2];
+                    }
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        let grid = grid
+            .into_iter()
+            .map(|row| row.into_iter().collect::<Option<Vec<_>>>())
+            .collect::<Option<Vec<_>>>()?;
+        if build(&grid[0], self.op).as_ref() == Some(&grid) {
+            Some(grid)
+        } else {
+            None
+        }
+    }
+
+    /// whether every brick is an `op` the rules allow with a result they
+    /// accept, as for a problem of the generator behind `rules`
+    pub fn valid<R: Rules>(&self, rules: &R) -> bool {
+        self.rows.windows(2).all(|pair| {
+            pair[0]
+                .windows(2)
+                .zip(&pair[1])
+                .all(|(w, &top)| rules.allows(self.op, w[0], w[1]) && rules.result(top))
+        })
+    }
+
+    /// a pyramid with `base` bricks at the bottom drawn from the rules'
+    /// operands and `hidden` bricks hidden, `None` when the bricks break
+    /// the rules or arithmetic cannot recover that many, and for any `op`
+    /// but `Add` and `Mul`
+    pub fn random<R: Rules, G: Rng>(rules: &R, base: usize, op: Op, hidden: usize, rng: &mut G) -> Option<Pyramid> {
+        let range = rules.operands();
+        if base == 0 || range.start >= range.end || !matches!(op, Op::Add | Op::Mul) {
+            return None;
+        }
+        let bottom = (0..base).map(|_| rng.gen_range(range.start, range.end)).collect::<Vec<_>>();
+        let rows = build(&bottom, op)?;
+        let mut p = Pyramid {
+            op,
+            hidden: rows.iter().map(|row| vec![false; row.len()]).collect(),
+            rows,
+        };
+        if !p.valid(rules) {
+            return None;
+        }
+
+        let mut bricks = p
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(k, row)| (0..row.len()).map(move |i| (k, i)))
+            .collect::<Vec<_>>();
+        bricks.shuffle(rng);
+
+        let mut count = 0;
+        for (k, i) in bricks {
+            if count == hidden {
+                break;
+            }
+            p.hidden[k][i] = true;
+            if p.solve().is_some() {
+                count += 1;
+            } else {
+                p.hidden[k][i] = false;
+            }
+        }
+        if count == hidden {
+            Some(p)
+        } else {
+            None
+        }
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.hidden.iter().flatten().filter(|&&h| h).count()
+    }
+}
+
+/// rows from the top down, `__` for hidden bricks
+impl fmt::Display for Pyramid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let base = self.rows.len();
+        for row in self.shown().iter().rev() {
+            let bricks = row
+                .iter()
+                .map(|v| v.map_or("__".to_string(), |v| v.to_string()))
+                .map(|s| format!("{:^6}", s))
+                .collect::<String>();
+            writeln!(f, "{}{}", " ".repeat(3 * (base - row.len())), bricks)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::target::Natural;
+
+    #[test]
+    fn pyramids() {
+        let rows = build(&[3, 5, 2], Op::Add).unwrap();
+        assert_eq!(rows, vec![vec![3, 5, 2], vec![8, 7], vec![15]]);
+
+        // one brick a row leaves the rest to arithmetic
+        let mut p = Pyramid {
+            op: Op::Add,
+            hidden: vec![vec![true, false, true], vec![true, false], vec![false]],
+            rows,
+        };
+        assert_eq!(p.solve(), Some(p.rows.clone()));
+        // the middle brick needs an equation, 3 + 2 x _ + 2 = 15
+        p.hidden = vec![vec![false, true, false], vec![true, true], vec![false]];
+        assert_eq!(p.solve(), None);
+
+        let p = Pyramid { op: Op::Mul, hidden: vec![vec![false, true], vec![false]], rows: build(&[0, 4], Op::Mul).unwrap() };
+        assert_eq!(p.solve(), None);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let p = (0..100).find_map(|_| Pyramid::random(&Natural(1..10), 4, Op::Add, 6, &mut rng)).unwrap();
+            assert_eq!(p.hidden_count(), 6, "{}", p);
+            assert_eq!(p.solve(), Some(p.rows.clone()));
+        }
+        assert_eq!(Pyramid::random(&Natural(1..10), 3, Op::Minus, 0, &mut rng), None);
+    }
+}
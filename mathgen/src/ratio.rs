@@ -0,0 +1,157 @@
+//! Exact fractions as a numeric backend for `Expr`.
+//!
+//! `Expr<Ratio>` divides without truncating, which percent and ratio
+//! problems need. `decimal` tells whether a value prints cleanly.
+
+use crate::math::*;
+use std::cmp::Ordering;
+use std::fmt;
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// fraction in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    num: i64,
+    den: i64,
+}
+
+impl Ratio {
+    /// `None` when `den` is zero
+    pub fn new(num: i64, den: i64) -> Option<Ratio> {
+        if den == 0 {
+            return None;
+        }
+        let g = gcd(num, den);
+        let sign = den.signum();
+        Some(Ratio {
+            num: sign * num / g,
+            den: sign * den / g,
+        })
+    }
+
+    pub fn from_int(v: i64) -> Ratio {
+        Ratio { num: v, den: 1 }
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i64 {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn checked_add(&self, o: &Ratio) -> Option<Ratio> {
+        let num = self.num.checked_mul(o.den)?.checked_add(o.num.checked_mul(self.den)?)?;
+        Ratio::new(num, self.den.checked_mul(o.den)?)
+    }
+
+    pub fn checked_sub(&self, o: &Ratio) -> Option<Ratio> {
+        self.checked_add(&Ratio { num: o.num.checked_neg()?, den: o.den })
+    }
+
+    pub fn checked_mul(&self, o: &Ratio) -> Option<Ratio> {
+        Ratio::new(self.num.checked_mul(o.num)?, self.den.checked_mul(o.den)?)
+    }
+
+    pub fn checked_div(&self, o: &Ratio) -> Option<Ratio> {
+        Ratio::new(self.num.checked_mul(o.den)?, self.den.checked_mul(o.num)?)
+    }
+
+    /// the value with at most `places` decimals and no trailing zeros, e.g.
+    /// `12.5`, `None` when it needs more
+    pub fn decimal(&self, places: u32) -> Option<String> {
+        let scale = 10i64.checked_pow(places)?;
+        if scale % self.den != 0 {
+            return None;
+        }
+        let scaled = self.num.checked_mul(scale / self.den)?;
+        let (int, frac) = (scaled.abs() / scale, scaled.abs() % scale);
+        let sign = if scaled < 0 { "-" } else { "" };
+        if frac == 0 {
+            return Some(format!("{}{}", sign, int));
+        }
+        let digits = format!("{:0width$}", frac, width = places as usize);
+        Some(format!("{}{}.{}", sign, int, digits.trim_end_matches('0')))
+    }
+}
+
+impl Ord for Ratio {
+    fn cmp(&self, o: &Ratio) -> Ordering {
+        (i128::from(self.num) * i128::from(o.den)).cmp(&(i128::from(o.num) * i128::from(self.den)))
+    }
+}
+
+impl PartialOrd for Ratio {
+    fn partial_cmp(&self, o: &Ratio) -> Option<Ordering> {
+        Some(self.cmp(o))
+    }
+}
+
+/// `3/4`, or `3` for whole numbers
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl Number for Ratio {
+    fn from_i32(v: i32) -> Self {
+        Ratio::from_int(i64::from(v))
+    }
+
+    fn checked_apply(op: Op, a: &Self, b: &Self) -> Option<Self> {
+        match op {
+            Op::Add => a.checked_add(b),
+            Op::Minus => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_div(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Expr::*;
+
+    #[test]
+    fn exact_fractions() {
+        let r = |n, d| Ratio::new(n, d).unwrap();
+        assert_eq!(r(6, -8).to_string(), "-3/4");
+        assert_eq!(r(4, 2).to_string(), "2");
+        assert!(r(4, 2).is_integer());
+        assert_eq!(Ratio::new(1, 0), None);
+
+        assert_eq!(r(1, 2).checked_add(&r(1, 3)), Some(r(5, 6)));
+        assert_eq!(r(1, 2).checked_sub(&r(3, 4)), Some(r(-1, 4)));
+        assert_eq!(r(2, 3).checked_mul(&r(9, 4)), Some(r(3, 2)));
+        assert_eq!(r(1, 2).checked_div(&Ratio::from_int(0)), None);
+        assert_eq!(Ratio::from_int(i64::MAX).checked_add(&Ratio::from_int(1)), None);
+        assert!(r(1, 3) < r(1, 2) && r(-1, 2) < r(1, 3));
+
+        assert_eq!(r(25, 2).decimal(1), Some("12.5".to_string()));
+        assert_eq!(r(-1, 8).decimal(3), Some("-0.125".to_string()));
+        assert_eq!(r(1, 3).decimal(3), None);
+
+        // 3-8/3 is a third, not 1 as with truncating division
+        let third = Primitive(Op::Minus, Ratio::from_int(3), r(8, 3));
+        let e = Compound(Op::Div, Box::new(Single(Ratio::from_int(8))), Box::new(third));
+        assert_eq!(e.checked_eval(), Some(Ratio::from_int(24)));
+    }
+}
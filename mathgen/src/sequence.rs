@@ -0,0 +1,289 @@
+//! Number patterns (找规律): fill in the blanks of a sequence.
+//!
+//! Rules go from simple to hard: a fixed step, a fixed ratio, two steps
+//! taking turns, and a formula of the position `n` such as `n x n + 1`.
+//! A problem only stands when the terms left visible pin down the simplest
+//! rule they follow, and that rule is what the answer key prints.
+
+use crate::math::*;
+use std::fmt;
+
+/// largest step tried when looking for alternating rules
+const MAX_STEP: i32 = 30;
+/// largest ratio tried when looking for geometric rules
+const MAX_RATIO: i32 = 10;
+
+/// a formula of the position `n`, such as `n x (n + 1)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    N,
+    Number(i32),
+    Node(Op, Box<Formula>, Box<Formula>),
+}
+
+fn is_additive(op: Op) -> bool {
+    op == Op::Add || op == Op::Minus
+}
+
+impl Formula {
+    pub fn node(op: Op, l: Formula, r: Formula) -> Formula {
+        Formula::Node(op, Box::new(l), Box::new(r))
+    }
+
+    /// value at position `n`, `None` on overflow or a remainder
+    pub fn at(&self, n: i32) -> Option<i32> {
+        match self {
+            Formula::N => Some(n),
+            Formula::Number(v) => Some(*v),
+            Formula::Node(op, l, r) => {
+                let (l, r) = (l.at(n)?, r.at(n)?);
+                if *op == Op::Div && (r == 0 || l % r != 0) {
+                    return None;
+                }
+                op.checked_apply(&l, &r)
+            }
+        }
+    }
+}
+
+/// `n x (n + 1)`, brackets only where they are needed
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (op, l, r) = match self {
+            Formula::N => return write!(f, "n"),
+            Formula::Number(v) => return write!(f, "{}", v),
+            Formula::Node(op, l, r) => (*op, l, r),
+        };
+        let bracket = |child: &Formula, right: bool| match child {
+            Formula::Node(c, _, _) => {
+                let (p, c) = (is_additive(op), is_additive(*c));
+                (c && !p) || (right && p == c && (op == Op::Minus || op == Op::Div))
+            }
+            _ => false,
+        };
+
+        match bracket(l, false) {
+            true => write!(f, "({})", l)?,
+            false => write!(f, "{}", l)?,
+        }
+        match bracket(r, true) {
+            true => write!(f, " {} ({})", op, r),
+            false => write!(f, " {} {}", op, r),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    Arithmetic { start: i32, step: i32 },
+    Geometric { start: i32, ratio: i32 },
+    /// steps alternate: 1, 4, 6, 9, 11 is +3 then +2
+    Alternating { start: i32, steps: (i32, i32) },
+    /// term `n`, counting from 1, is the formula at `n`
+    Formula(Formula),
+}
+
+impl Rule {
+    /// term at position `i`, counting from 0
+    pub fn term(&self, i: usize) -> Option<i32> {
+        let n = i as i32;
+        match self {
+            Rule::Arithmetic { start, step } => start.checked_add(step.checked_mul(n)?),
+            Rule::Geometric { start, ratio } => start.checked_mul(ratio.checked_pow(i as u32)?),
+            Rule::Alternating { start, steps } => {
+                let pair = steps.0.checked_add(steps.1)?.checked_mul(n / 2)?;
+                start.checked_add(pair)?.checked_add(steps.0.checked_mul(n % 2)?)
+            }
+            Rule::Formula(formula) => formula.at(n.checked_add(1)?),
+        }
+    }
+
+    pub fn terms(&self, len: usize) -> Option<Vec<i32>> {
+        (0..len).map(|i| self.term(i)).collect()
+    }
+
+    /// simple rules of `kind` (0 arithmetic, 1 geometric, 2 alternating)
+    /// that the visible terms follow
+    fn fits(kind: usize, visible: &[(usize, i32)]) -> Vec<Rule> {
+        let (i, first) = visible[0];
+        let n = i as i32;
+        let candidates = match kind {
+            0 => {
+                let (j, second) = visible[1];
+                let d = (j - i) as i32;
+                let rise = match second.checked_sub(first) {
+                    Some(rise) if rise % d == 0 => rise,
+                    _ => return Vec::new(),
+                };
+                let step = rise / d;
+                match step.checked_mul(n).and_then(|v| first.checked_sub(v)) {
+                    Some(start) => vec![Rule::Arithmetic { start, step }],
+                    None => Vec::new(),
+                }
+            }
+            1 => (2..=MAX_RATIO)
+                .filter_map(|ratio| {
+                    let r = ratio.checked_pow(i as u32)?;
+                    if first % r == 0 {
+                        Some(Rule::Geometric { start: first / r, ratio })
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+            _ => {
+                let mut out = Vec::new();
+                for a in -MAX_STEP..=MAX_STEP {
+                    for b in -MAX_STEP..=MAX_STEP {
+                        let start = (a + b)
+                            .checked_mul(n / 2)
+                            .and_then(|v| v.checked_add(a * (n % 2)))
+                            .and_then(|v| first.checked_sub(v));
+                        match start {
+                            Some(start) if a != b => out.push(Rule::Alternating { start, steps: (a, b) }),
+                            _ => {}
+                        }
+                    }
+                }
+                out
+            }
+        };
+
+        candidates
+            .into_iter()
+            .filter(|r| visible.iter().all(|&(i, v)| r.term(i) == Some(v)))
+            .collect()
+    }
+}
+
+/// `每次+3`, `第n个数: n x n + 1`
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Arithmetic { step, .. } if *step < 0 => write!(f, "每次-{}", -step),
+            Rule::Arithmetic { step, .. } => write!(f, "每次+{}", step),
+            Rule::Geometric { ratio, .. } => write!(f, "每次x{}", ratio),
+            Rule::Alternating { steps: (a, b), .. } => write!(f, "{:+}, {:+} 交替", a, b),
+            Rule::Formula(formula) => write!(f, "第n个数: {}", formula),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceProblem {
+    /// the simplest rule the visible terms follow
+    pub rule: Rule,
+    pub terms: Vec<i32>,
+    /// positions left blank, sorted
+    pub blanks: Vec<usize>,
+}
+
+impl SequenceProblem {
+    /// `len` terms of `rule` with `blanks` hidden, `None` when the visible
+    /// terms do not settle on a single simplest rule. A formula that is
+    /// really a simpler rule, like `n x 3 - 1`, is replaced by it.
+    pub fn new(rule: Rule, len: usize, blanks: &[usize]) -> Option<SequenceProblem> {
+        let terms = rule.terms(len)?;
+        let mut blanks = blanks.to_vec();
+        blanks.sort();
+        blanks.dedup();
+
+        let visible = terms
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !blanks.contains(i))
+            .map(|(i, &v)| (i, v))
+            .collect::<Vec<_>>();
+        if visible.len() < 3 || blanks.iter().any(|&b| b >= len) {
+            return None;
+        }
+
+        for kind in 0..3 {
+            let fits = Rule::fits(kind, &visible);
+            match fits.as_slice() {
+                [] => continue,
+                [simplest] if simplest.terms(len).as_ref() == Some(&terms) => {
+                    return Some(SequenceProblem {
+                        rule: simplest.clone(),
+                        terms,
+                        blanks,
+                    });
+                }
+                _ => return None,
+            }
+        }
+
+        match rule {
+            Rule::Formula(_) => Some(SequenceProblem { rule, terms, blanks }),
+            _ => None,
+        }
+    }
+
+    pub fn answers(&self) -> Vec<i32> {
+        self.blanks.iter().map(|&i| self.terms[i]).collect()
+    }
+
+    /// the terms with every blank written as `blank`, or filled in
+    pub fn show(&self, blank: &str, filled: bool) -> Vec<String> {
+        self.terms
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if filled || !self.blanks.contains(&i) {
+                    v.to_string()
+                } else {
+                    blank.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// `2, 5, 8, __, 14, __`
+impl fmt::Display for SequenceProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.show("__", false).join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence_problems() {
+        let p = SequenceProblem::new(Rule::Arithmetic { start: 2, step: 3 }, 6, &[3, 5]).unwrap();
+        assert_eq!(p.to_string(), "2, 5, 8, __, 14, __");
+        assert_eq!(p.answers(), vec![11, 17]);
+        assert_eq!(p.rule.to_string(), "每次+3");
+
+        use Formula::*;
+        let rule = Rule::Formula(Formula::node(Op::Minus, Formula::node(Op::Mul, N, Number(3)), Number(1)));
+        let p = SequenceProblem::new(rule, 5, &[4]).unwrap();
+        assert_eq!(p.rule, Rule::Arithmetic { start: 2, step: 3 });
+
+        let pronic = Formula::node(Op::Mul, N, Formula::node(Op::Add, N, Number(1)));
+        assert_eq!(pronic.to_string(), "n x (n + 1)");
+        assert_eq!(pronic.at(3), Some(12));
+        let rule = Rule::Formula(Formula::node(Op::Add, Formula::node(Op::Mul, N, N), Number(1)));
+        assert_eq!(rule.to_string(), "第n个数: n x n + 1");
+        let p = SequenceProblem::new(rule.clone(), 6, &[2]).unwrap();
+        assert_eq!(p.terms, vec![2, 5, 10, 17, 26, 37]);
+        assert_eq!(p.rule, rule);
+
+        let alt = Rule::Alternating { start: 1, steps: (3, 2) };
+        assert_eq!(alt.terms(5), Some(vec![1, 4, 6, 9, 11]));
+        assert_eq!(SequenceProblem::new(alt, 6, &[4]).unwrap().answers(), vec![11]);
+
+        // 1, 2, 4 reads as doubling rather than +1, +2
+        let alt = Rule::Alternating { start: 1, steps: (1, 2) };
+        assert!(SequenceProblem::new(alt, 5, &[3, 4]).is_none());
+        let double = Rule::Geometric { start: 1, ratio: 2 };
+        assert_eq!(SequenceProblem::new(double, 5, &[3, 4]).unwrap().answers(), vec![8, 16]);
+
+        // the last three of twenty powers of 3 step too far for a sum
+        let powers = Rule::Geometric { start: 1, ratio: 3 };
+        let p = SequenceProblem::new(powers.clone(), 20, &(0..17).collect::<Vec<_>>()).unwrap();
+        assert_eq!(p.rule, powers);
+    }
+}
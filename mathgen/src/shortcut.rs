@@ -0,0 +1,342 @@
+//! Mental-math shortcuts (简便运算).
+//!
+//! `detect` looks for a place in an `Expr` where the commutative,
+//! associative or distributive law turns the work into round numbers, and
+//! returns the rewritten expression for the answer key.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// reorder factors so a friendly pair goes first: 25x37x4 = 25x4x37
+    RegroupProduct,
+    /// reorder addends so a pair makes a round number: 37+48+63 = 37+63+48
+    RegroupSum,
+    /// split a factor to pair it with 25 or 125: 25x44 = 25x4x11
+    SplitFactor,
+    /// pull the common factor out: 99x46+46 = (99+1)x46
+    FactorOut,
+    /// split a factor close to a round number: 46x101 = 46x100+46x1
+    Expand,
+    /// drop the brackets after a minus: 438-(138+57) = 438-138-57
+    SubtractSum,
+    /// take two subtrahends together: 500-73-27 = 500-(73+27)
+    GroupSubtrahends,
+    /// a pair known by heart: 125x8 = 1000
+    FriendlyPair,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Strategy::RegroupProduct => "乘法交换律、结合律",
+            Strategy::RegroupSum => "加法交换律、结合律",
+            Strategy::SplitFactor => "拆数凑整",
+            Strategy::FactorOut => "乘法分配律（提取公因数）",
+            Strategy::Expand => "乘法分配律（拆分）",
+            Strategy::SubtractSum => "减法的性质（去括号）",
+            Strategy::GroupSubtrahends => "减法的性质（添括号）",
+            Strategy::FriendlyPair => "凑整（25x4=100, 125x8=1000）",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Shortcut {
+    pub strategy: Strategy,
+    pub original: Expr,
+    pub rewritten: Expr,
+}
+
+/// answer key line: `25x37x4=25x4x37=3700`, or `125x8=1000` when the
+/// shortcut is the answer itself
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.rewritten {
+            Single(v) => write!(f, "{}={}", self.original, v),
+            _ => write!(f, "{}={}={}", self.original, self.rewritten, self.rewritten.eval()),
+        }
+    }
+}
+
+/// 25 and 125 with the factor that rounds them up
+const FRIENDLY: [(i32, i32); 2] = [(25, 4), (125, 8)];
+
+fn is_round(v: i32) -> bool {
+    v != 0 && v % 10 == 0
+}
+
+fn zeros(v: i32) -> u32 {
+    let (mut v, mut n) = (v, 0);
+    while is_round(v) {
+        v /= 10;
+        n += 1;
+    }
+    n
+}
+
+/// operator and both operands, a `Primitive` gives two `Single`s
+fn parts(e: &Expr) -> Option<(Op, Expr, Expr)> {
+    match e {
+        Single(_) => None,
+        Primitive(op, a, b) => Some((*op, Single(*a), Single(*b))),
+        Compound(op, a, b) => Some((*op, a.as_ref().clone(), b.as_ref().clone())),
+    }
+}
+
+/// operands of a run of `op`, left to right
+fn flatten(e: &Expr, op: Op, out: &mut Vec<Expr>) {
+    match parts(e) {
+        Some((o, l, r)) if o == op => {
+            flatten(&l, op, out);
+            flatten(&r, op, out);
+        }
+        _ => out.push(e.clone()),
+    }
+}
+
+fn chain(op: Op, items: Vec<Expr>) -> Expr {
+    let mut it = items.into_iter();
+    let first = it.next().expect("chain needs an operand");
+    it.fold(first, |acc, e| Expr::join(op, acc, e))
+}
+
+/// in a run of `+` or `x`, move the pair that combines to the roundest
+/// number to the front, unless it is computed first already
+fn regroup(e: &Expr, op: Op) -> Option<Expr> {
+    let mut items = Vec::new();
+    flatten(e, op, &mut items);
+    if items.len() < 3 {
+        return None;
+    }
+
+    let values = items.iter().map(Expr::checked_eval).collect::<Option<Vec<_>>>()?;
+    let mut best = None;
+    for i in 0..values.len() {
+        for j in i + 1..values.len() {
+            if is_round(values[i]) || is_round(values[j]) {
+                continue;
+            }
+            let v = op.checked_apply(&values[i], &values[j])?;
+            let better = match best {
+                Some((_, _, z)) => zeros(v) > z,
+                None => true,
+            };
+            if is_round(v) && better {
+                best = Some((i, j, zeros(v)));
+            }
+        }
+    }
+
+    match best {
+        Some((0, 1, _)) | None => None,
+        Some((i, j, _)) => {
+            let b = items.remove(j);
+            let a = items.remove(i);
+            let mut regrouped = vec![a, b];
+            regrouped.extend(items);
+            Some(chain(op, regrouped))
+        }
+    }
+}
+
+/// `25 x 44` => `25 x 4 x 11`
+fn split_factor(l: &Expr, r: &Expr) -> Option<Expr> {
+    let (lv, rv) = (l.checked_eval()?, r.checked_eval()?);
+    for &(v, partner) in FRIENDLY.iter() {
+        for &(a, b, swapped) in [(lv, rv, false), (rv, lv, true)].iter() {
+            if a == v && b != partner && b % partner == 0 {
+                let items = if swapped {
+                    vec![Single(b / partner), Single(partner), Single(a)]
+                } else {
+                    vec![Single(a), Single(partner), Single(b / partner)]
+                };
+                return Some(chain(Op::Mul, items));
+            }
+        }
+    }
+    None
+}
+
+/// `125 x 8` => `1000`, a friendly pair on its own
+fn friendly_pair(l: &Expr, r: &Expr) -> Option<Expr> {
+    let (lv, rv) = (l.checked_eval()?, r.checked_eval()?);
+    FRIENDLY
+        .iter()
+        .find(|&&(v, partner)| (lv, rv) == (v, partner) || (rv, lv) == (v, partner))
+        .map(|&(v, partner)| Single(v * partner))
+}
+
+/// `46 x 101` => `46x100+46x1`, `46 x 98` => `46x100-46x2`
+fn expand(l: &Expr, r: &Expr) -> Option<Expr> {
+    let (lv, rv) = (l.checked_eval()?, r.checked_eval()?);
+    for &(n, other, right) in [(rv, l, true), (lv, r, false)].iter() {
+        match other.checked_eval() {
+            Some(v) if !is_round(v) => {}
+            _ => continue,
+        }
+        let hundreds = match n.checked_add(50) {
+            Some(v) => v / 100 * 100,
+            None => continue,
+        };
+        let d = n - hundreds;
+        if hundreds == 0 || d == 0 || d.abs() > 2 {
+            continue;
+        }
+
+        let op = if d > 0 { Op::Add } else { Op::Minus };
+        let term = |v| {
+            if right {
+                Expr::join(Op::Mul, other.clone(), Single(v))
+            } else {
+                Expr::join(Op::Mul, Single(v), other.clone())
+            }
+        };
+        return Some(Expr::join(op, term(hundreds), term(d.abs())));
+    }
+    None
+}
+
+/// ways to read `e` as `common x other`, a plain number is `n x 1`
+fn factorings(e: &Expr) -> Vec<(i32, Expr, bool)> {
+    match parts(e) {
+        Some((Op::Mul, l, r)) => {
+            let mut fs = Vec::new();
+            if let Some(v) = r.checked_eval() {
+                fs.push((v, l.clone(), true));
+            }
+            if let Some(v) = l.checked_eval() {
+                fs.push((v, r, false));
+            }
+            fs
+        }
+        _ => match e {
+            Single(v) => vec![(*v, Single(1), true)],
+            _ => vec![],
+        },
+    }
+}
+
+/// `a x b op a x c` => `(b op c) x a` when `b op c` is round
+fn factor_out(op: Op, l: &Expr, r: &Expr) -> Option<Expr> {
+    for (cl, ol, right) in factorings(l) {
+        for (cr, or, _) in factorings(r) {
+            if cl != cr || cl == 1 {
+                continue;
+            }
+            let sum = Expr::join(op, ol.clone(), or);
+            match sum.checked_eval() {
+                Some(v) if is_round(v) && v > 0 => {}
+                _ => continue,
+            }
+            return Some(if right {
+                Expr::join(Op::Mul, sum, Single(cl))
+            } else {
+                Expr::join(Op::Mul, Single(cl), sum)
+            });
+        }
+    }
+    None
+}
+
+/// `a-(b+c)` => `a-b-c` when `a-b` or `a-c` is round
+fn subtract_sum(l: &Expr, r: &Expr) -> Option<Expr> {
+    let (b, c) = match parts(r) {
+        Some((Op::Add, b, c)) => (b, c),
+        _ => return None,
+    };
+    let a = l.checked_eval()?;
+    for (first, second) in [(b.clone(), c.clone()), (c, b)].iter() {
+        let rest = first.checked_eval().and_then(|v| a.checked_sub(v));
+        if matches!(rest, Some(v) if is_round(v)) {
+            let head = Expr::join(Op::Minus, l.clone(), first.clone());
+            return Some(Expr::join(Op::Minus, head, second.clone()));
+        }
+    }
+    None
+}
+
+/// `a-b-c` => `a-(b+c)` when `b+c` is round
+fn group_subtrahends(l: &Expr, r: &Expr) -> Option<Expr> {
+    let (a, b) = match parts(l) {
+        Some((Op::Minus, a, b)) => (a, b),
+        _ => return None,
+    };
+    let (bv, cv) = (b.checked_eval()?, r.checked_eval()?);
+    if is_round(bv) || is_round(cv) || !matches!(bv.checked_add(cv), Some(v) if is_round(v)) {
+        return None;
+    }
+    Some(Expr::join(Op::Minus, a, Expr::join(Op::Add, b, r.clone())))
+}
+
+fn detect_here(e: &Expr) -> Option<(Strategy, Expr)> {
+    let (op, l, r) = parts(e)?;
+    match op {
+        Op::Mul => regroup(e, op)
+            .map(|x| (Strategy::RegroupProduct, x))
+            .or_else(|| split_factor(&l, &r).map(|x| (Strategy::SplitFactor, x)))
+            .or_else(|| expand(&l, &r).map(|x| (Strategy::Expand, x)))
+            .or_else(|| friendly_pair(&l, &r).map(|x| (Strategy::FriendlyPair, x))),
+        Op::Add => factor_out(op, &l, &r)
+            .map(|x| (Strategy::FactorOut, x))
+            .or_else(|| regroup(e, op).map(|x| (Strategy::RegroupSum, x))),
+        Op::Minus => factor_out(op, &l, &r)
+            .map(|x| (Strategy::FactorOut, x))
+            .or_else(|| subtract_sum(&l, &r).map(|x| (Strategy::SubtractSum, x)))
+            .or_else(|| group_subtrahends(&l, &r).map(|x| (Strategy::GroupSubtrahends, x))),
+        Op::Div => None,
+    }
+}
+
+/// first shortcut found, trying the whole expression before its operands
+pub fn detect(e: &Expr) -> Option<Shortcut> {
+    fn walk(e: &Expr) -> Option<(Strategy, Expr)> {
+        if let Some(found) = detect_here(e) {
+            return Some(found);
+        }
+        match e {
+            Compound(op, l, r) => walk(l)
+                .map(|(s, x)| (s, Expr::join(*op, x, r.as_ref().clone())))
+                .or_else(|| walk(r).map(|(s, x)| (s, Expr::join(*op, l.as_ref().clone(), x)))),
+            _ => None,
+        }
+    }
+
+    walk(e).map(|(strategy, rewritten)| Shortcut {
+        strategy,
+        original: e.clone(),
+        rewritten,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(e: Expr, strategy: Strategy, rewritten: &str) {
+        let s = detect(&e).unwrap_or_else(|| panic!("no shortcut for {}", e));
+        assert_eq!(s.strategy, strategy);
+        assert_eq!(s.rewritten.to_string(), rewritten);
+        assert_eq!(s.rewritten.eval(), e.eval());
+    }
+
+    #[test]
+    fn shortcuts() {
+        let b = Box::new;
+        check(Compound(Op::Mul, b(Primitive(Op::Mul, 25, 37)), b(Single(4))), Strategy::RegroupProduct, "25x4x37");
+        check(Compound(Op::Add, b(Primitive(Op::Add, 37, 48)), b(Single(63))), Strategy::RegroupSum, "37+63+48");
+        check(Primitive(Op::Mul, 125, 32), Strategy::SplitFactor, "125x8x4");
+        check(Compound(Op::Add, b(Primitive(Op::Mul, 99, 46)), b(Single(46))), Strategy::FactorOut, "(99+1)x46");
+        check(Primitive(Op::Mul, 46, 101), Strategy::Expand, "46x100+46x1");
+        check(Compound(Op::Minus, b(Single(438)), b(Primitive(Op::Add, 138, 57))), Strategy::SubtractSum, "438-138-57");
+        check(Compound(Op::Minus, b(Primitive(Op::Minus, 500, 73)), b(Single(27))), Strategy::GroupSubtrahends, "500-(73+27)");
+        check(Primitive(Op::Mul, 125, 8), Strategy::FriendlyPair, "1000");
+        assert_eq!(detect(&Primitive(Op::Mul, 4, 25)).unwrap().to_string(), "4x25=100");
+        assert!(detect(&Primitive(Op::Add, i32::MAX, 1)).is_none());
+        assert!(detect(&Primitive(Op::Mul, 46, i32::MAX)).is_none());
+        assert!(detect(&Primitive(Op::Add, 37, 48)).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! Problems read aloud for dictation (听算).
+//!
+//! `speak` follows the brackets `Display` prints: a bracketed part is read
+//! as what it makes, `十二与三的和乘四` or `the sum of twelve and three,
+//! times four`, so the listener hears the grouping without bracket words.
+//! `script` turns a list of problems into a plain script or SSML for a
+//! text-to-speech tool.
+
+use crate::chinese::to_chinese;
+use crate::math::Expr::*;
+use crate::math::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Chinese,
+    English,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptFormat {
+    /// one numbered line a problem
+    Plain,
+    /// `<speak>` document with a pause after each problem
+    Ssml { pause_secs: u32 },
+}
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+const TENS: [&str; 10] = ["", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+const SCALES: [(u64, &str); 3] = [(1_000_000_000, "billion"), (1_000_000, "million"), (1000, "thousand")];
+
+/// `n` in English words, `one hundred twenty-three`
+pub fn to_english(n: i64) -> String {
+    if n < 0 {
+        return format!("negative {}", words(n.unsigned_abs()));
+    }
+    words(n as u64)
+}
+
+fn words(n: u64) -> String {
+    fn below_thousand(n: u64) -> String {
+        let mut words = Vec::new();
+        if n >= 100 {
+            words.push(format!("{} hundred", ONES[(n / 100) as usize]));
+        }
+        match n % 100 {
+            0 if n >= 100 => {}
+            r @ 0..=19 => words.push(ONES[r as usize].to_string()),
+            r if r % 10 == 0 => words.push(TENS[(r / 10) as usize].to_string()),
+            r => words.push(format!("{}-{}", TENS[(r / 10) as usize], ONES[(r % 10) as usize])),
+        }
+        words.join(" ")
+    }
+
+    let (mut rest, mut out) = (n, Vec::new());
+    for &(scale, name) in SCALES.iter() {
+        if rest >= scale {
+            out.push(format!("{} {}", words(rest / scale), name));
+            rest %= scale;
+        }
+    }
+    if rest > 0 || out.is_empty() {
+        out.push(below_thousand(rest));
+    }
+    out.join(" ")
+}
+
+fn number(v: i32, lang: Language) -> String {
+    match lang {
+        Language::Chinese => to_chinese(i64::from(v)).expect("i32 has words"),
+        Language::English => to_english(i64::from(v)),
+    }
+}
+
+fn verb(op: Op, lang: Language) -> &'static str {
+    match (lang, op) {
+        (Language::Chinese, Op::Add) => "加",
+        (Language::Chinese, Op::Minus) => "减",
+        (Language::Chinese, Op::Mul) => "乘",
+        (Language::Chinese, Op::Div) => "除以",
+        (Language::English, Op::Add) => "plus",
+        (Language::English, Op::Minus) => "minus",
+        (Language::English, Op::Mul) => "times",
+        (Language::English, Op::Div) => "divided by",
+    }
+}
+
+/// what `op` makes: 和, 差, 积, 商
+fn result(op: Op, lang: Language) -> &'static str {
+    match (lang, op) {
+        (Language::Chinese, Op::Add) => "和",
+        (Language::Chinese, Op::Minus) => "差",
+        (Language::Chinese, Op::Mul) => "积",
+        (Language::Chinese, Op::Div) => "商",
+        (Language::English, Op::Add) => "sum",
+        (Language::English, Op::Minus) => "difference",
+        (Language::English, Op::Mul) => "product",
+        (Language::English, Op::Div) => "quotient",
+    }
+}
+
+fn parts(e: &Expr) -> Option<(Op, Expr, Expr)> {
+    match e {
+        Single(_) => None,
+        Primitive(op, a, b) => Some((*op, Single(*a), Single(*b))),
+        Compound(op, l, r) => Some((*op, (**l).clone(), (**r).clone())),
+    }
+}
+
+/// whether `Display` brackets `child` under `op`, as its right operand if
+/// `right`
+fn bracketed(op: Op, child: &Expr, right: bool) -> bool {
+    let inner = match child {
+        Single(_) => return false,
+        Primitive(op2, _, _) | Compound(op2, _, _) => *op2,
+    };
+    let additive = inner == Op::Add || inner == Op::Minus;
+    match (op, right) {
+        (Op::Add, _) => false,
+        (Op::Minus, false) => false,
+        (Op::Minus, true) => additive,
+        (_, false) => additive,
+        (_, true) => additive || inner == Op::Div || op == Op::Div,
+    }
+}
+
+/// `e` read aloud
+pub fn speak(e: &Expr, lang: Language) -> String {
+    let (op, l, r) = match (e, parts(e)) {
+        (Single(v), _) => return number(*v, lang),
+        (_, p) => p.expect("operations have parts"),
+    };
+
+    let side = |child: &Expr, right: bool| {
+        if !bracketed(op, child, right) {
+            return speak(child, lang);
+        }
+        let (inner, a, b) = parts(child).expect("only operations are bracketed");
+        let (a, b) = (speak(&a, lang), speak(&b, lang));
+        match lang {
+            Language::Chinese => format!("{}与{}的{}", a, b, result(inner, lang)),
+            Language::English if right => format!("the {} of {} and {}", result(inner, lang), a, b),
+            Language::English => format!("the {} of {} and {},", result(inner, lang), a, b),
+        }
+    };
+
+    let (l, r) = (side(&l, false), side(&r, true));
+    match lang {
+        Language::Chinese => format!("{}{}{}", l, verb(op, lang), r),
+        Language::English => format!("{} {} {}", l, verb(op, lang), r),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// every problem read aloud, numbered, in `format`
+pub fn script(problems: &[Expr], lang: Language, format: ScriptFormat) -> String {
+    let lines = problems.iter().enumerate().map(|(i, e)| match lang {
+        Language::Chinese => (format!("第{}题", number(i as i32 + 1, lang)), speak(e, lang)),
+        Language::English => (format!("Number {}", number(i as i32 + 1, lang)), speak(e, lang)),
+    });
+
+    match format {
+        ScriptFormat::Plain => lines.map(|(n, s)| format!("{}: {}\n", n, s)).collect(),
+        ScriptFormat::Ssml { pause_secs } => {
+            let code = match lang {
+                Language::Chinese => "zh-CN",
+                Language::English => "en-US",
+            };
+            let mut out = format!("<speak version=\"1.0\" xml:lang=\"{}\">\n", code);
+            for (n, s) in lines {
+                out += &format!(
+                    "  <p><s>{}</s><s>{}</s></p>\n  <break time=\"{}s\"/>\n",
+                    escape(&n),
+                    escape(&s),
+                    pause_secs
+                );
+            }
+            out += "</speak>\n";
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spoken_problems() {
+        let b = Box::new;
+        let e = Compound(Op::Add, b(Single(12)), b(Primitive(Op::Mul, 3, 4)));
+        assert_eq!(speak(&e, Language::Chinese), "十二加三乘四");
+        assert_eq!(speak(&e, Language::English), "twelve plus three times four");
+
+        let e = Compound(Op::Mul, b(Primitive(Op::Add, 12, 3)), b(Single(4)));
+        assert_eq!(speak(&e, Language::Chinese), "十二与三的和乘四");
+        assert_eq!(speak(&e, Language::English), "the sum of twelve and three, times four");
+        let e = Compound(Op::Div, b(Single(100)), b(Primitive(Op::Minus, 30, 5)));
+        assert_eq!(speak(&e, Language::Chinese), "一百除以三十与五的差");
+
+        assert_eq!(to_english(3_050_021), "three million fifty thousand twenty-one");
+        assert_eq!(to_english(700), "seven hundred");
+
+        let s = script(&[Primitive(Op::Minus, 9, 4)], Language::Chinese, ScriptFormat::Plain);
+        assert_eq!(s, "第一题: 九减四\n");
+        let s = script(&[Primitive(Op::Minus, 9, 4)], Language::English, ScriptFormat::Ssml { pause_secs: 5 });
+        assert!(s.contains("<s>nine minus four</s>") && s.contains("<break time=\"5s\"/>"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Expressions that reach a given value.
+//!
+//! The generators aim for a result range, this goes the other way round:
+//! fix the value, the operators and the shape, and list every expression
+//! that evaluates to exactly that value. Operands come either from the
+//! rules' range or from a fixed set of numbers each used once, as in
+//! "make 24 with 3, 3, 8, 8".
+
+use crate::enumerate::{self, Rules, Shape};
+use crate::math::Expr::*;
+use crate::math::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+/// whole numbers only: no negative differences, no remainders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Natural(pub Range<i32>);
+
+impl Rules for Natural {
+    fn operands(&self) -> Range<i32> {
+        self.0.clone()
+    }
+
+    fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+        match op {
+            Op::Minus => l >= r,
+            Op::Div => r != 0 && l % r == 0,
+            _ => true,
+        }
+    }
+
+    fn result(&self, value: i32) -> bool {
+        value >= 0
+    }
+}
+
+/// `rules` restricted to the operators in `ops` and to results equal to
+/// `value`
+pub struct Target<'a, R: Rules> {
+    pub rules: &'a R,
+    pub value: i32,
+    pub ops: OpSet,
+}
+
+impl<'a, R: Rules> Rules for Target<'a, R> {
+    fn operands(&self) -> Range<i32> {
+        self.rules.operands()
+    }
+
+    fn operands_of(&self, op: Op, right: bool) -> Range<i32> {
+        self.rules.operands_of(op, right)
+    }
+
+    fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+        self.ops.contains(op) && self.rules.allows(op, l, r)
+    }
+
+    fn result(&self, value: i32) -> bool {
+        value == self.value && self.rules.result(value)
+    }
+}
+
+type Built = Vec<(Expr, i32)>;
+
+impl<'a, R: Rules> Target<'a, R> {
+    pub fn new(rules: &'a R, value: i32, ops: OpSet) -> Self {
+        Target { rules, value, ops }
+    }
+
+    /// every expression of `shape` with operands from the rules' range
+    pub fn list(&self, shape: &Shape) -> Vec<Expr> {
+        enumerate::list(self, shape)
+    }
+
+    pub fn list_all(&self, leaves: usize) -> Vec<Expr> {
+        enumerate::list_all(self, leaves)
+    }
+
+    pub fn count(&self, shape: &Shape) -> u64 {
+        enumerate::count(self, shape)
+    }
+
+    /// every expression of `shape` using each of `numbers` exactly once, in
+    /// any order. Repeated numbers do not repeat expressions.
+    pub fn using(&self, numbers: &[i32], shape: &Shape) -> Vec<Expr> {
+        if shape.leaves() != numbers.len() {
+            return Vec::new();
+        }
+
+        let mut sorted = numbers.to_vec();
+        sorted.sort();
+        let mut memo = HashMap::new();
+        self.build(shape, &sorted, &mut memo)
+            .into_iter()
+            .filter(|(_, v)| self.result(*v))
+            .map(|(e, _)| e)
+            .collect()
+    }
+
+    /// like `using`, over every shape
+    pub fn using_all(&self, numbers: &[i32]) -> Vec<Expr> {
+        Shape::all(numbers.len())
+            .iter()
+            .flat_map(|s| self.using(numbers, s))
+            .collect()
+    }
+
+    /// a random expression of `shape` that reaches the target, `numbers`
+    /// being the operands to use once or `None` for the rules' range. Lists
+    /// them all first, so keep the range small.
+    pub fn pick<G: Rng + ?Sized>(&self, rng: &mut G, shape: &Shape, numbers: Option<&[i32]>) -> Option<Expr> {
+        let all = match numbers {
+            Some(numbers) => self.using(numbers, shape),
+            None => self.list(shape),
+        };
+        all.choose(rng).cloned()
+    }
+
+    /// trees of `shape` over the sorted multiset `numbers`, with values
+    fn build(&self, shape: &Shape, numbers: &[i32], memo: &mut HashMap<(Shape, Vec<i32>), Built>) -> Built {
+        let key = (shape.clone(), numbers.to_vec());
+        if let Some(built) = memo.get(&key) {
+            return built.clone();
+        }
+
+        let mut out = Vec::new();
+        match shape {
+            Shape::Leaf => out.push((Single(numbers[0]), numbers[0])),
+            Shape::Node(l, r) => {
+                let mut seen = HashSet::new();
+                for (left, right) in splits(numbers, l.leaves()) {
+                    if !seen.insert(left.clone()) {
+                        continue;
+                    }
+                    let ls = self.build(l, &left, memo);
+                    let rs = self.build(r, &right, memo);
+                    for (le, lv) in &ls {
+                        for (re, rv) in &rs {
+                            for op in self.ops.iter() {
+                                if !self.rules.allows(op, *lv, *rv) {
+                                    continue;
+                                }
+                                if let Some(v) = op.checked_apply(lv, rv) {
+                                    out.push((Expr::join(op, le.clone(), re.clone()), v));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        memo.insert(key, out.clone());
+        out
+    }
+}
+
+/// every way to take `n` of `numbers` to the left and the rest to the
+/// right, both kept sorted. None for 32 numbers or more, far too many
+/// ways to list
+pub fn splits(numbers: &[i32], n: usize) -> Vec<(Vec<i32>, Vec<i32>)> {
+    if numbers.len() >= 32 {
+        return Vec::new();
+    }
+    (0u32..1 << numbers.len())
+        .filter(|mask| mask.count_ones() as usize == n)
+        .map(|mask| {
+            let (mut l, mut r) = (Vec::new(), Vec::new());
+            for (i, &v) in numbers.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    l.push(v);
+                } else {
+                    r.push(v);
+                }
+            }
+            (l, r)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reach_target() {
+        let rules = Natural(1..10);
+        let pair = Shape::node(Shape::Leaf, Shape::Leaf);
+        let t = Target::new(&rules, 12, OpSet::ALL);
+        // 3+9..9+3, 3x4, 4x3, 2x6, 6x2
+        assert_eq!(t.count(&pair), 11);
+        assert!(t.list(&pair).iter().all(|e| e.eval() == 12));
+
+        let t = Target::new(&rules, 24, OpSet::ALL);
+        // only 8÷(3-8÷3) works, which needs fractions
+        assert!(t.using_all(&[3, 3, 8, 8]).is_empty());
+        let all = t.using_all(&[1, 2, 3, 4]);
+        assert!(all.iter().all(|e| e.eval() == 24));
+        assert!(all.iter().any(|e| e.to_string() == "(1+2+3)x4"));
+
+        let add = Target::new(&rules, 4, OpSet::empty().with(Op::Add));
+        assert_eq!(add.using(&[2, 2], &pair).len(), 1);
+        assert!(add.using(&[1, 2], &pair).is_empty());
+
+        assert_eq!(splits(&[1, 2, 3], 1).len(), 3);
+        assert!(splits(&[1; 40], 2).is_empty());
+    }
+}
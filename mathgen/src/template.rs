@@ -0,0 +1,378 @@
+//! Expression shape templates.
+//!
+//! A template is written like an expression: `_` is an operand the
+//! generator picks, a number is kept as is, and an operator is either a
+//! plain `+ - x ÷`, a set like `{+,-}` or `?` for any of the four:
+//!
+//! ```text
+//! (_ {+,-} _) x _
+//! _ - _ ÷ _
+//! ```
+//!
+//! Precedence is the usual one. A set binds like `+` and `-` unless it only
+//! holds `x` and `÷`, so brackets make mixed sets explicit.
+
+use crate::enumerate::Rules;
+use crate::math::Expr::*;
+use crate::math::*;
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// attempts before `fill` gives up on a template the rules cannot satisfy
+const MAX_TRIES: usize = 10000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    /// operand picked by the generator
+    Slot,
+    Number(i32),
+    Node(OpSet, Box<Template>, Box<Template>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    /// character offset the parser stopped at
+    pub pos: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "template error at {}: {}", self.pos, self.msg)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+fn is_additive(ops: OpSet) -> bool {
+    ops.contains(Op::Add) || ops.contains(Op::Minus)
+}
+
+fn op_of(c: char) -> Option<Op> {
+    match c {
+        '+' => Some(Op::Add),
+        '-' => Some(Op::Minus),
+        'x' | 'X' | '*' | '×' => Some(Op::Mul),
+        '÷' | '/' => Some(Op::Div),
+        _ => None,
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error<T>(&self, msg: &str) -> Result<T, TemplateError> {
+        Err(TemplateError {
+            pos: self.pos,
+            msg: msg.to_string(),
+        })
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).cloned()
+    }
+
+    /// operator at the cursor, consumed only if `want` accepts it
+    fn operator(&mut self, want: fn(OpSet) -> bool) -> Result<Option<OpSet>, TemplateError> {
+        let start = self.pos;
+        let ops = match self.peek() {
+            Some('?') => {
+                self.pos += 1;
+                OpSet::ALL
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut ops = OpSet::empty();
+                loop {
+                    match self.peek() {
+                        Some('}') => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(',') => self.pos += 1,
+                        Some(c) => match op_of(c) {
+                            Some(op) => {
+                                ops = ops.with(op);
+                                self.pos += 1;
+                            }
+                            None => return self.error("expected an operator in the set"),
+                        },
+                        None => return self.error("unclosed operator set"),
+                    }
+                }
+                if ops.is_empty() {
+                    return self.error("empty operator set");
+                }
+                ops
+            }
+            Some(c) => match op_of(c) {
+                Some(op) => {
+                    self.pos += 1;
+                    OpSet::empty().with(op)
+                }
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        if want(ops) {
+            Ok(Some(ops))
+        } else {
+            self.pos = start;
+            Ok(None)
+        }
+    }
+
+    fn expr(&mut self) -> Result<Template, TemplateError> {
+        let mut t = self.term()?;
+        while let Some(ops) = self.operator(is_additive)? {
+            t = Template::Node(ops, Box::new(t), Box::new(self.term()?));
+        }
+        Ok(t)
+    }
+
+    fn term(&mut self) -> Result<Template, TemplateError> {
+        let mut t = self.atom()?;
+        while let Some(ops) = self.operator(|ops| !is_additive(ops))? {
+            t = Template::Node(ops, Box::new(t), Box::new(self.atom()?));
+        }
+        Ok(t)
+    }
+
+    fn atom(&mut self) -> Result<Template, TemplateError> {
+        match self.peek() {
+            Some('_') => {
+                self.pos += 1;
+                Ok(Template::Slot)
+            }
+            Some('(') => {
+                self.pos += 1;
+                let t = self.expr()?;
+                if self.peek() != Some(')') {
+                    return self.error("expected ')'");
+                }
+                self.pos += 1;
+                Ok(t)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.pos < self.chars.len() && self.chars[self.pos].is_ascii_digit() {
+                    self.pos += 1;
+                }
+                let s: String = self.chars[start..self.pos].iter().collect();
+                match s.parse() {
+                    Ok(v) => Ok(Template::Number(v)),
+                    Err(_) => self.error("number too large"),
+                }
+            }
+            _ => self.error("expected '_', a number or '('"),
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let t = p.expr()?;
+        if p.peek().is_some() {
+            return p.error("unexpected trailing input");
+        }
+        Ok(t)
+    }
+}
+
+impl Template {
+    pub fn node(ops: OpSet, l: Template, r: Template) -> Template {
+        Template::Node(ops, Box::new(l), Box::new(r))
+    }
+
+    /// number of operands, slots and fixed numbers alike
+    pub fn leaves(&self) -> usize {
+        match self {
+            Template::Slot | Template::Number(_) => 1,
+            Template::Node(_, l, r) => l.leaves() + r.leaves(),
+        }
+    }
+
+    /// whether `e` has this shape, operators and fixed numbers
+    pub fn matches(&self, e: &Expr) -> bool {
+        match (self, e) {
+            (Template::Slot, Single(_)) => true,
+            (Template::Number(n), Single(v)) => n == v,
+            (Template::Node(ops, l, r), Primitive(op, a, b)) => {
+                ops.contains(*op) && l.matches(&Single(*a)) && r.matches(&Single(*b))
+            }
+            (Template::Node(ops, l, r), Compound(op, a, b)) => ops.contains(*op) && l.matches(a) && r.matches(b),
+            _ => false,
+        }
+    }
+
+    /// random expression of this template whose nodes `rules` allows and
+    /// whose value is a valid result, `None` if none turned up
+    pub fn fill<R: Rules, G: Rng + ?Sized>(&self, rules: &R, rng: &mut G) -> Option<Expr> {
+        for _ in 0..MAX_TRIES {
+            if let Some((e, v)) = self.fill_node(rules, rng, None) {
+                if rules.result(v) {
+                    return Some(e);
+                }
+            }
+        }
+        None
+    }
+
+    /// `parent` is the operator above and whether this is its right operand
+    fn fill_node<R: Rules, G: Rng + ?Sized>(
+        &self,
+        rules: &R,
+        rng: &mut G,
+        parent: Option<(Op, bool)>,
+    ) -> Option<(Expr, i32)> {
+        match self {
+            Template::Slot => {
+                let r = match parent {
+                    Some((op, right)) => rules.operands_of(op, right),
+                    None => rules.operands(),
+                };
+                if r.start >= r.end {
+                    return None;
+                }
+                let v = rng.gen_range(r.start, r.end);
+                Some((Single(v), v))
+            }
+            Template::Number(n) => Some((Single(*n), *n)),
+            Template::Node(ops, l, r) => {
+                let choices = ops.iter().collect::<Vec<_>>();
+                for _ in 0..100 {
+                    let op = choices[rng.gen_range(0, choices.len())];
+                    let (le, lv) = l.fill_node(rules, rng, Some((op, false)))?;
+                    let (re, rv) = r.fill_node(rules, rng, Some((op, true)))?;
+                    if !rules.allows(op, lv, rv) {
+                        continue;
+                    }
+                    if let Some(v) = op.checked_apply(&lv, &rv) {
+                        return Some((Expr::join(op, le, re), v));
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn show_ops(f: &mut fmt::Formatter, ops: OpSet) -> fmt::Result {
+            let list = ops.iter().collect::<Vec<_>>();
+            match list.len() {
+                1 => write!(f, "{}", list[0]),
+                4 => write!(f, "?"),
+                _ => {
+                    let s = list.iter().map(|op| op.to_string()).collect::<Vec<_>>();
+                    write!(f, "{{{}}}", s.join(","))
+                }
+            }
+        }
+
+        /// whether `child` needs brackets under an operator of `parent`, a
+        /// right `÷` is bracketed under `x` too, as `Expr` shows it
+        fn bracket(parent: OpSet, child: &Template, right: bool) -> bool {
+            match child {
+                Template::Node(ops, _, _) => {
+                    let (p, c) = (is_additive(parent), is_additive(*ops));
+                    let div = !c && ops.contains(Op::Div);
+                    (c && !p) || (right && p == c && (parent.contains(Op::Minus) || parent.contains(Op::Div) || div))
+                }
+                _ => false,
+            }
+        }
+
+        match self {
+            Template::Slot => write!(f, "_"),
+            Template::Number(n) => write!(f, "{}", n),
+            Template::Node(ops, l, r) => {
+                if bracket(*ops, l, false) {
+                    write!(f, "({})", l)?;
+                } else {
+                    write!(f, "{}", l)?;
+                }
+                write!(f, " ")?;
+                show_ops(f, *ops)?;
+                if bracket(*ops, r, true) {
+                    write!(f, " ({})", r)
+                } else {
+                    write!(f, " {}", r)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Small;
+
+    impl Rules for Small {
+        fn operands(&self) -> std::ops::Range<i32> {
+            2..10
+        }
+
+        fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+            match op {
+                Op::Minus => l > r,
+                Op::Div => r != 0 && l % r == 0,
+                _ => true,
+            }
+        }
+
+        fn result(&self, value: i32) -> bool {
+            value < 100
+        }
+    }
+
+    #[test]
+    fn parse_and_fill() {
+        let t: Template = "(_ {+,-} _) x _".parse().unwrap();
+        assert_eq!(t.to_string(), "(_ {+,-} _) x _");
+        assert_eq!(t.leaves(), 3);
+
+        let t2: Template = "_ - _ ÷ 3".parse().unwrap();
+        match &t2 {
+            Template::Node(ops, _, r) => {
+                assert_eq!(*ops, OpSet::empty().with(Op::Minus));
+                assert_eq!(**r, Template::node(OpSet::empty().with(Op::Div), Template::Slot, Template::Number(3)));
+            }
+            _ => panic!("bad parse {:?}", t2),
+        }
+        assert_eq!("_ - (_ - _)".parse::<Template>().unwrap().to_string(), "_ - (_ - _)");
+        for s in ["_ x (_ ÷ _)", "_ ÷ (_ x _)", "_ - (_ + _) x 2", "_ ÷ _ x (_ {x,÷} _)"].iter() {
+            let t: Template = s.parse().unwrap();
+            assert_eq!(t.to_string(), *s);
+            assert_eq!(t.to_string().parse::<Template>().unwrap(), t);
+        }
+        assert!("_ + ".parse::<Template>().is_err());
+        assert!("(_ {+,%} _)".parse::<Template>().is_err());
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let e = t.fill(&Small, &mut rng).unwrap();
+            assert!(t.matches(&e), "{}", e);
+            assert!(e.eval() < 100);
+            let e = t2.fill(&Small, &mut rng).unwrap();
+            assert!(t2.matches(&e), "{}", e);
+        }
+    }
+}
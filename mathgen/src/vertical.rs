@@ -0,0 +1,321 @@
+//! Column arithmetic (竖式) layout.
+//!
+//! `VerticalForm` places every digit of a column addition, subtraction or
+//! multiplication into a fixed grid, together with the carry/borrow marks,
+//! partial products and the result row. It knows nothing about fonts or
+//! surfaces, so any backend can walk the rows and draw them.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Operand,
+    Partial,
+    Result,
+}
+
+/// small mark written next to a digit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// carry coming into this column
+    Carry(u8),
+    /// this digit lent one to the column on its right
+    Borrow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub kind: RowKind,
+    /// operator sign drawn to the left of the digits
+    pub op: Option<Op>,
+    pub value: i32,
+    /// one cell per column, left to right, `None` is blank
+    pub cells: Vec<Option<u8>>,
+    /// marks produced while computing this row, aligned with `cells`
+    pub marks: Vec<Option<Mark>>,
+    /// draw a horizontal rule between this row and the one above
+    pub rule_above: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerticalForm {
+    pub op: Op,
+    pub columns: usize,
+    pub rows: Vec<Row>,
+}
+
+/// row before it is aligned into columns, everything indexed by place (0 = ones)
+struct Line {
+    kind: RowKind,
+    op: Option<Op>,
+    value: i32,
+    digits: Vec<u8>,
+    shift: usize,
+    marks: Vec<Option<Mark>>,
+    rule_above: bool,
+}
+
+impl Line {
+    fn new(kind: RowKind, value: i32) -> Line {
+        let digits = digits_of(value);
+        let marks = vec![None; digits.len()];
+        Line {
+            kind,
+            op: None,
+            value,
+            digits,
+            shift: 0,
+            marks,
+            rule_above: false,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.digits.len().max(self.marks.len()) + self.shift
+    }
+
+    fn mark(&mut self, place: usize, m: Mark) {
+        if self.marks.len() <= place {
+            self.marks.resize(place + 1, None);
+        }
+        self.marks[place] = Some(m);
+    }
+}
+
+/// decimal digits, ones first
+fn digits_of(v: i32) -> Vec<u8> {
+    let mut v = v;
+    let mut ds = vec![(v % 10) as u8];
+    v /= 10;
+    while v > 0 {
+        ds.push((v % 10) as u8);
+        v /= 10;
+    }
+    ds
+}
+
+fn digit(ds: &[u8], place: usize) -> u8 {
+    ds.get(place).cloned().unwrap_or(0)
+}
+
+impl VerticalForm {
+    /// lay out `a op b` in columns. Division is not a column form and
+    /// negative operands or results, or ones that overflow, are rejected.
+    pub fn new(op: Op, a: i32, b: i32) -> Option<VerticalForm> {
+        if a < 0 || b < 0 {
+            return None;
+        }
+
+        let lines = match op {
+            Op::Add => Self::addition(a, b)?,
+            Op::Minus if a >= b => Self::subtraction(a, b),
+            Op::Mul => Self::multiplication(a, b)?,
+            _ => return None,
+        };
+
+        Some(Self::align(op, lines))
+    }
+
+    /// only a `Primitive` or a `Compound` of two plain numbers has a column form
+    pub fn from_expr(e: &Expr) -> Option<VerticalForm> {
+        match e {
+            Primitive(op, v1, v2) => Self::new(*op, *v1, *v2),
+            Compound(op, v1, v2) => match (v1.as_ref(), v2.as_ref()) {
+                (Single(l), Single(r)) => Self::new(*op, *l, *r),
+                _ => None,
+            },
+            Single(_) => None,
+        }
+    }
+
+    pub fn result(&self) -> &Row {
+        self.rows.last().expect("vertical form always has a result row")
+    }
+
+    fn addition(a: i32, b: i32) -> Option<Vec<Line>> {
+        let top = Line::new(RowKind::Operand, a);
+        let mut bottom = Line::new(RowKind::Operand, b);
+        bottom.op = Some(Op::Add);
+
+        let mut res = Line::new(RowKind::Result, a.checked_add(b)?);
+        res.rule_above = true;
+
+        let n = top.digits.len().max(bottom.digits.len());
+        let mut carry = 0;
+        for place in 0..n {
+            if carry > 0 {
+                res.mark(place, Mark::Carry(carry));
+            }
+            carry = (digit(&top.digits, place) + digit(&bottom.digits, place) + carry) / 10;
+        }
+
+        Some(vec![top, bottom, res])
+    }
+
+    fn subtraction(a: i32, b: i32) -> Vec<Line> {
+        let mut top = Line::new(RowKind::Operand, a);
+        let mut bottom = Line::new(RowKind::Operand, b);
+        bottom.op = Some(Op::Minus);
+
+        let mut res = Line::new(RowKind::Result, a - b);
+        res.rule_above = true;
+
+        let mut borrow = 0;
+        for place in 0..top.digits.len() {
+            let d = digit(&top.digits, place) as i32 - borrow - digit(&bottom.digits, place) as i32;
+            borrow = if d < 0 { 1 } else { 0 };
+            if borrow > 0 {
+                top.mark(place + 1, Mark::Borrow);
+            }
+        }
+
+        vec![top, bottom, res]
+    }
+
+    fn multiplication(a: i32, b: i32) -> Option<Vec<Line>> {
+        let top = Line::new(RowKind::Operand, a);
+        let mut bottom = Line::new(RowKind::Operand, b);
+        bottom.op = Some(Op::Mul);
+
+        // one partial product per non-zero digit of the multiplier
+        let mut partials = bottom
+            .digits
+            .iter()
+            .enumerate()
+            .filter(|&(_, &d)| d != 0)
+            .map(|(shift, &d)| {
+                let mut p = Line::new(RowKind::Partial, a.checked_mul(d as i32)?);
+                p.shift = shift;
+                let mut carry = 0;
+                for place in 0..top.digits.len() {
+                    if carry > 0 {
+                        p.mark(place, Mark::Carry(carry));
+                    }
+                    carry = (digit(&top.digits, place) * d + carry) / 10;
+                }
+                Some(p)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut res = Line::new(RowKind::Result, a.checked_mul(b)?);
+        res.rule_above = true;
+
+        if partials.len() < 2 {
+            // a single partial product is already the answer
+            if let Some(p) = partials.pop() {
+                for (place, m) in p.marks.iter().enumerate() {
+                    if let Some(m) = m {
+                        res.mark(place + p.shift, *m);
+                    }
+                }
+            }
+            return Some(vec![top, bottom, res]);
+        }
+
+        partials[0].rule_above = true;
+        let n = partials.iter().map(Line::width).max().unwrap_or(0);
+        let mut carry = 0;
+        for place in 0..n {
+            if carry > 0 {
+                res.mark(place, Mark::Carry(carry));
+            }
+            let sum: u8 = partials
+                .iter()
+                .filter(|p| place >= p.shift)
+                .map(|p| digit(&p.digits, place - p.shift))
+                .sum();
+            carry = (sum + carry) / 10;
+        }
+
+        let mut lines = vec![top, bottom];
+        lines.extend(partials);
+        lines.push(res);
+        Some(lines)
+    }
+
+    fn align(op: Op, lines: Vec<Line>) -> VerticalForm {
+        let columns = lines.iter().map(Line::width).max().unwrap_or(1);
+        let rows = lines
+            .into_iter()
+            .map(|l| {
+                let mut cells = vec![None; columns];
+                let mut marks = vec![None; columns];
+                for (place, d) in l.digits.iter().enumerate() {
+                    cells[columns - 1 - place - l.shift] = Some(*d);
+                }
+                for (place, m) in l.marks.iter().enumerate() {
+                    marks[columns - 1 - place - l.shift] = *m;
+                }
+                Row {
+                    kind: l.kind,
+                    op: l.op,
+                    value: l.value,
+                    cells,
+                    marks,
+                    rule_above: l.rule_above,
+                }
+            })
+            .collect();
+
+        VerticalForm { op, columns, rows }
+    }
+}
+
+/// plain text layout, one character per column with the sign in front
+impl fmt::Display for VerticalForm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in &self.rows {
+            if row.rule_above {
+                writeln!(f, "{}", "-".repeat(self.columns + 1))?;
+            }
+            match row.op {
+                Some(op) => write!(f, "{}", op)?,
+                None => write!(f, " ")?,
+            }
+            for c in &row.cells {
+                match c {
+                    Some(d) => write!(f, "{}", d)?,
+                    None => write!(f, " ")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_forms() {
+        let v = VerticalForm::new(Op::Add, 127, 45).unwrap();
+        assert_eq!(v.columns, 3);
+        assert_eq!(v.result().value, 172);
+        assert_eq!(v.result().marks, vec![None, Some(Mark::Carry(1)), None]);
+        assert_eq!(v.rows[1].cells, vec![None, Some(4), Some(5)]);
+
+        let v = VerticalForm::new(Op::Minus, 305, 128).unwrap();
+        assert_eq!(v.result().cells, vec![Some(1), Some(7), Some(7)]);
+        assert_eq!(v.rows[0].marks, vec![Some(Mark::Borrow), Some(Mark::Borrow), None]);
+        assert!(VerticalForm::new(Op::Minus, 3, 12).is_none());
+
+        let v = VerticalForm::new(Op::Mul, 123, 45).unwrap();
+        let kinds = v.rows.iter().map(|r| r.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![RowKind::Operand, RowKind::Operand, RowKind::Partial, RowKind::Partial, RowKind::Result]);
+        assert_eq!(v.rows[2].value, 615);
+        assert_eq!(v.rows[3].cells, vec![Some(4), Some(9), Some(2), None]);
+        assert_eq!(v.result().value, 5535);
+        assert_eq!(format!("{}", v).lines().last(), Some(" 5535"));
+
+        let v = VerticalForm::new(Op::Mul, 36, 7).unwrap();
+        assert_eq!(v.rows.len(), 3);
+        assert_eq!(v.result().marks, vec![None, Some(Mark::Carry(4)), None]);
+
+        assert!(VerticalForm::new(Op::Mul, 100000, 100000).is_none());
+        assert!(VerticalForm::new(Op::Add, i32::MAX, 1).is_none());
+    }
+}
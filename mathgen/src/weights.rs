@@ -0,0 +1,211 @@
+//! Operator weights for the random generators.
+//!
+//! `OpWeights` replaces the uniform operator pick, `OpPlan` adds overrides
+//! per depth in the tree (depth 0 is the root), and `OpStats` reports the
+//! operator mix that actually made it through rejection.
+
+use crate::math::Expr::*;
+use crate::math::*;
+use rand::Rng;
+use std::fmt;
+
+fn index(op: Op) -> usize {
+    match op {
+        Op::Add => 0,
+        Op::Minus => 1,
+        Op::Mul => 2,
+        Op::Div => 3,
+    }
+}
+
+/// relative odds of each operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpWeights([u32; 4]);
+
+impl OpWeights {
+    pub fn uniform() -> Self {
+        OpWeights([1; 4])
+    }
+
+    /// always `op`
+    pub fn only(op: Op) -> Self {
+        OpWeights([0; 4]).with(op, 1)
+    }
+
+    /// equal odds for the operators in `ops`, none for the rest
+    pub fn from_set(ops: OpSet) -> Self {
+        ops.iter().fold(OpWeights([0; 4]), |w, op| w.with(op, 1))
+    }
+
+    pub fn with(mut self, op: Op, weight: u32) -> Self {
+        self.0[index(op)] = weight;
+        self
+    }
+
+    pub fn weight(&self, op: Op) -> u32 {
+        self.0[index(op)]
+    }
+
+    /// panics when every weight is zero
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Op {
+        let total: u32 = self.0.iter().sum();
+        assert!(total > 0, "at least one operator needs a weight");
+
+        let mut x = rng.gen_range(0, total);
+        for &op in Op::ALL.iter() {
+            if x < self.weight(op) {
+                return op;
+            }
+            x -= self.weight(op);
+        }
+        unreachable!()
+    }
+}
+
+impl Default for OpWeights {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+/// operator weights for every position in the tree: `by_depth[d]`
+/// overrides `default` for nodes at depth `d`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpPlan {
+    pub default: OpWeights,
+    pub by_depth: Vec<Option<OpWeights>>,
+}
+
+impl OpPlan {
+    pub fn new(default: OpWeights) -> Self {
+        OpPlan {
+            default,
+            by_depth: Vec::new(),
+        }
+    }
+
+    pub fn at_depth(mut self, depth: usize, w: OpWeights) -> Self {
+        if self.by_depth.len() <= depth {
+            self.by_depth.resize(depth + 1, None);
+        }
+        self.by_depth[depth] = Some(w);
+        self
+    }
+
+    /// e.g. `OpPlan::default().root(OpWeights::only(Op::Mul))`
+    pub fn root(self, w: OpWeights) -> Self {
+        self.at_depth(0, w)
+    }
+
+    pub fn weights(&self, depth: usize) -> &OpWeights {
+        match self.by_depth.get(depth) {
+            Some(Some(w)) => w,
+            _ => &self.default,
+        }
+    }
+
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R, depth: usize) -> Op {
+        self.weights(depth).pick(rng)
+    }
+
+    /// whether a tree `depths` levels deep can use `op` anywhere
+    pub fn can_pick(&self, op: Op, depths: usize) -> bool {
+        (0..depths).any(|d| self.weights(d).weight(op) > 0)
+    }
+}
+
+/// operator frequencies over the problems a generator returned
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub problems: u64,
+    counts: [u64; 4],
+    root: [u64; 4],
+}
+
+impl OpStats {
+    pub fn record<N: Number>(&mut self, e: &Expr<N>) {
+        fn walk<N: Number>(counts: &mut [u64; 4], e: &Expr<N>) {
+            match e {
+                Single(_) => {}
+                Primitive(op, _, _) => counts[index(*op)] += 1,
+                Compound(op, l, r) => {
+                    counts[index(*op)] += 1;
+                    walk(counts, l);
+                    walk(counts, r);
+                }
+            }
+        }
+
+        self.problems += 1;
+        walk(&mut self.counts, e);
+        match e {
+            Primitive(op, _, _) | Compound(op, _, _) => self.root[index(*op)] += 1,
+            Single(_) => {}
+        }
+    }
+
+    /// nodes using `op`, anywhere in the tree
+    pub fn count(&self, op: Op) -> u64 {
+        self.counts[index(op)]
+    }
+
+    /// share of all operator nodes that use `op`
+    pub fn share(&self, op: Op) -> f64 {
+        let total: u64 = self.counts.iter().sum();
+        if total == 0 {
+            0.0
+        } else {
+            self.count(op) as f64 / total as f64
+        }
+    }
+
+    /// share of problems whose root is `op`
+    pub fn root_share(&self, op: Op) -> f64 {
+        if self.problems == 0 {
+            0.0
+        } else {
+            self.root[index(op)] as f64 / self.problems as f64
+        }
+    }
+}
+
+/// one line per operator: `x  123  30.5%  (root 50.0%)`
+impl fmt::Display for OpStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} problems", self.problems)?;
+        for &op in Op::ALL.iter() {
+            writeln!(
+                f,
+                "{}  {:6}  {:5.1}%  (root {:5.1}%)",
+                op,
+                self.count(op),
+                self.share(op) * 100.0,
+                self.root_share(op) * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_pick() {
+        let mut rng = rand::thread_rng();
+        let w = OpWeights::uniform().with(Op::Minus, 3).with(Op::Div, 0);
+        let mut stats = OpStats::default();
+        for _ in 0..6000 {
+            stats.record(&Primitive(w.pick(&mut rng), 1, 1));
+        }
+        assert_eq!(stats.count(Op::Div), 0);
+        assert!((stats.share(Op::Minus) - 0.6).abs() < 0.05);
+
+        let plan = OpPlan::default().root(OpWeights::only(Op::Mul));
+        assert!((0..100).all(|_| plan.pick(&mut rng, 0) == Op::Mul));
+        assert_eq!(plan.weights(3), &OpWeights::uniform());
+        assert!(plan.can_pick(Op::Add, 2));
+        assert!(!plan.can_pick(Op::Add, 1));
+    }
+}
@@ -0,0 +1,216 @@
+use mathgen::anzan::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::ops::Range;
+
+const ROW_HEIGHT: f64 = 20.0;
+const COLUMN_WIDTH: f64 = 100.0;
+const CARD_WIDTH: f64 = 170.0;
+const CARD_HEIGHT: f64 = 110.0;
+const CARDS_PER_ROW: usize = 3;
+/// rows of cards that fit an A4 page under the title
+const CARD_ROWS: usize = 6;
+
+/// Generates column lists for abacus practice. Every number has a digit
+/// count from `digits`, `minus_share` of the rows after the first are
+/// subtracted, and with `never_negative` the running total stays at zero
+/// or above, a subtraction that would break it is turned into an addition.
+/// The digit count is capped so that `rows` numbers add up within an i32.
+pub struct AnzanGen {
+    pub digits: Range<u32>,
+    pub rows: usize,
+    pub minus_share: f64,
+    pub never_negative: bool,
+
+    rng: ThreadRng,
+}
+
+impl AnzanGen {
+    pub fn new() -> Self {
+        AnzanGen {
+            digits: 2..3,
+            rows: 10,
+            minus_share: 0.3,
+            never_negative: true,
+            rng: thread_rng(),
+        }
+    }
+
+    /// most digits a number may have so that `rows` of them fit an i32
+    fn max_digits(&self) -> u32 {
+        let cap = i64::from(i32::MAX) / self.rows.max(1) as i64;
+        (cap + 1).to_string().len() as u32 - 1
+    }
+
+    /// number with a digit count from `digits`, at most `max`
+    fn number(&mut self, max: i32) -> Option<i32> {
+        let end = self.digits.end.min(self.max_digits() + 1).max(2);
+        let d = self.rng.gen_range(self.digits.start.clamp(1, end - 1), end);
+        let low = 10i32.pow(d - 1);
+        let high = 10i64.pow(d).min(i64::from(max) + 1) as i32;
+        if low >= high {
+            None
+        } else {
+            Some(self.rng.gen_range(low, high))
+        }
+    }
+
+    pub fn list(&mut self) -> ColumnList {
+        let mut rows = Vec::with_capacity(self.rows);
+        let mut total = 0;
+        for i in 0..self.rows {
+            let minus = i > 0 && self.rng.gen_bool(self.minus_share);
+            let limit = if self.never_negative { total } else { i32::MAX };
+            let v = match self.number(limit) {
+                Some(n) if minus => -n,
+                _ => self.number(i32::MAX).expect("digits fit an i32"),
+            };
+            // the digit cap keeps this within an i32
+            total += v;
+            rows.push(v);
+        }
+        ColumnList { rows }
+    }
+
+    pub fn lists(&mut self, n: usize) -> Vec<ColumnList> {
+        (0..n).map(|_| self.list()).collect()
+    }
+}
+
+impl Default for AnzanGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn right_aligned(cr: &Context, right: f64, y: f64, s: &str) {
+    let ext = cr.text_extents(s);
+    cr.move_to(right - ext.x_advance, y);
+    cr.show_text(s);
+}
+
+/// the list as a column with its right edge at x, subtracted rows carry a
+/// minus sign, `filled` writes the answer under the rule
+pub fn draw_column_list(cr: &Context, x: f64, y: f64, list: &ColumnList, filled: bool) {
+    let mut y = y;
+    for v in &list.rows {
+        y += ROW_HEIGHT;
+        right_aligned(cr, x, y, &v.to_string());
+    }
+
+    y += 8.0;
+    cr.set_line_width(1.0);
+    cr.move_to(x - COLUMN_WIDTH + 20.0, y);
+    cr.line_to(x, y);
+    cr.stroke();
+
+    if filled {
+        if let Some(answer) = list.answer() {
+            right_aligned(cr, x, y + ROW_HEIGHT, &answer.to_string());
+        }
+    }
+}
+
+/// one flash card with its top left corner at (x, y), the row's number in
+/// the middle with its sign
+pub fn draw_flash_card(cr: &Context, x: f64, y: f64, label: &str, text: &str) {
+    cr.set_line_width(1.0);
+    cr.rectangle(x, y, CARD_WIDTH, CARD_HEIGHT);
+    cr.stroke();
+
+    cr.save();
+    cr.set_font_size(10.0);
+    cr.move_to(x + 6.0, y + 14.0);
+    cr.show_text(label);
+
+    cr.set_font_size(36.0);
+    let ext = cr.text_extents(text);
+    cr.move_to(
+        x + (CARD_WIDTH - ext.width) / 2.0 - ext.x_bearing,
+        y + (CARD_HEIGHT - ext.height) / 2.0 - ext.y_bearing,
+    );
+    cr.show_text(text);
+    cr.restore();
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// column lists side by side in bands, a band that does not fit goes
+    /// on the next page
+    pub fn render_column_lists(&mut self, target: &Surface, lists: &[ColumnList], filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let per_row = 5;
+        let mut y = 60.0;
+        for chunk in lists.chunks(per_row) {
+            let rows = chunk.iter().map(|l| l.rows.len()).max().unwrap_or(0);
+            if y > 60.0 && y + (rows + 2) as f64 * ROW_HEIGHT > PAGE_BOTTOM {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+                y = 60.0;
+            }
+            for (i, l) in chunk.iter().enumerate() {
+                draw_column_list(&cr, 20.0 + (i + 1) as f64 * COLUMN_WIDTH, y, l, filled);
+            }
+            y += (rows + 3) as f64 * ROW_HEIGHT;
+        }
+    }
+
+    /// flash cards for one list, one number a card in reading order and as
+    /// many pages as it takes; the last card asks for the answer, or shows
+    /// it when `filled`
+    pub fn render_flash_cards(&mut self, target: &Surface, list: &ColumnList, filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut cards = list
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, v)| match i {
+                0 => v.to_string(),
+                _ => format!("{:+}", v),
+            })
+            .collect::<Vec<_>>();
+        let answer = list.answer().map(|v| v.to_string()).unwrap_or_default();
+        cards.push(if filled { format!("={}", answer) } else { "=?".to_string() });
+
+        let per_page = CARDS_PER_ROW * CARD_ROWS;
+        for (i, text) in cards.iter().enumerate() {
+            if i > 0 && i % per_page == 0 {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+            }
+            let (row, col) = (i % per_page / CARDS_PER_ROW, i % CARDS_PER_ROW);
+            let x = 30.0 + col as f64 * (CARD_WIDTH + 10.0);
+            let y = 60.0 + row as f64 * (CARD_HEIGHT + 10.0);
+            draw_flash_card(&cr, x, y, &(i + 1).to_string(), text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_total_stays_positive() {
+        let mut g = AnzanGen::new();
+        g.digits = 1..4;
+        g.minus_share = 0.6;
+        for l in g.lists(50) {
+            assert_eq!(l.rows.len(), 10);
+            assert!(l.never_negative(), "{}", l);
+        }
+
+        g.digits = 9..10;
+        g.rows = 30;
+        g.never_negative = false;
+        for l in g.lists(20) {
+            assert!(l.answer().is_some(), "{}", l);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use mathgen::bond::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+const RADIUS: f64 = 13.0;
+/// vertical distance from the whole to its parts
+const DROP: f64 = 45.0;
+/// horizontal distance from the whole to each part
+const SPREAD: f64 = 25.0;
+
+fn circled(cr: &Context, cx: f64, cy: f64, text: Option<String>) {
+    cr.new_path();
+    cr.arc(cx, cy, RADIUS, 0.0, 2.0 * PI);
+    cr.stroke();
+
+    if let Some(s) = text {
+        let ext = cr.text_extents(&s);
+        cr.move_to(cx - ext.width / 2.0 - ext.x_bearing, cy - ext.height / 2.0 - ext.y_bearing);
+        cr.show_text(&s);
+    }
+}
+
+/// line between two circles, from edge to edge
+fn branch(cr: &Context, (x1, y1): (f64, f64), (x2, y2): (f64, f64), r1: f64, r2: f64) {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let len = (dx * dx + dy * dy).sqrt();
+    cr.move_to(x1 + dx * r1 / len, y1 + dy * r1 / len);
+    cr.line_to(x2 - dx * r2 / len, y2 - dy * r2 / len);
+    cr.stroke();
+}
+
+/// bond diagram with the whole centered at (x, y) and the parts below it
+pub fn draw_number_bond(cr: &Context, x: f64, y: f64, bond: &NumberBond, filled: bool) {
+    let text = |slot| {
+        if filled || slot != bond.hidden {
+            Some(bond.value(slot).to_string())
+        } else {
+            None
+        }
+    };
+
+    let whole = (x, y);
+    let left = (x - SPREAD, y + DROP);
+    let right = (x + SPREAD, y + DROP);
+
+    cr.set_line_width(1.0);
+    circled(cr, whole.0, whole.1, text(BondSlot::Whole));
+    circled(cr, left.0, left.1, text(BondSlot::Left));
+    circled(cr, right.0, right.1, text(BondSlot::Right));
+    branch(cr, whole, left, RADIUS, RADIUS);
+    branch(cr, whole, right, RADIUS, RADIUS);
+}
+
+/// `a+b=` at (x, y) with the smaller addend branching into the part that
+/// makes ten and the rest. `filled` writes the parts and the full working.
+pub fn draw_make_ten(cr: &Context, x: f64, y: f64, m: &MakeTen, filled: bool) {
+    let (prefix, addend) = if m.a >= m.b {
+        (format!("{}{}", m.a, Op::Add), m.b.to_string())
+    } else {
+        (String::new(), m.a.to_string())
+    };
+
+    let line = if filled {
+        m.to_string()
+    } else {
+        format!("{}={}", m.steps()[0], " ".repeat(5))
+    };
+    cr.move_to(x, y);
+    cr.show_text(&line);
+
+    let start = x + cr.text_extents(&prefix).x_advance;
+    let top = (start + cr.text_extents(&addend).x_advance / 2.0, y + 4.0);
+    let r = RADIUS * 0.8;
+    let left = (top.0 - SPREAD * 0.6, y + DROP * 0.6);
+    let right = (top.0 + SPREAD * 0.6, y + DROP * 0.6);
+
+    cr.set_line_width(1.0);
+    branch(cr, top, left, 0.0, r);
+    branch(cr, top, right, 0.0, r);
+    for (c, v) in [(left, m.to_ten), (right, m.rest)].iter() {
+        cr.new_path();
+        cr.arc(c.0, c.1, r, 0.0, 2.0 * PI);
+        cr.stroke();
+        if filled {
+            let s = v.to_string();
+            let ext = cr.text_extents(&s);
+            cr.move_to(c.0 - ext.width / 2.0 - ext.x_bearing, c.1 - ext.height / 2.0 - ext.y_bearing);
+            cr.show_text(&s);
+        }
+    }
+}
+
+impl PrimitiveMathGen {
+    /// bond of a whole from `whole` split into two non-empty parts, `None`
+    /// when `whole` has no number of at least 2 to split
+    pub fn gen_number_bond(&mut self, whole: Range<i32>) -> Option<NumberBond> {
+        let wholes = whole.start.max(2)..whole.end;
+        if wholes.is_empty() {
+            return None;
+        }
+        let w = self.rand(wholes);
+        let left = self.rand(1..w);
+        let hidden = match self.rand(0..3) {
+            0 => BondSlot::Whole,
+            1 => BondSlot::Left,
+            _ => BondSlot::Right,
+        };
+        NumberBond::new(w, left, hidden)
+    }
+
+    pub fn gen_make_ten(&mut self) -> MakeTen {
+        loop {
+            if let Some(m) = MakeTen::new(self.rand(1..10), self.rand(1..10)) {
+                return m;
+            }
+        }
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// number bond page, five diagrams a row
+    pub fn render_number_bonds(&mut self, target: &Surface, bonds: &[NumberBond], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for chunk in bonds.chunks(5) {
+            for (i, b) in chunk.iter().enumerate() {
+                draw_number_bond(&cr, 70.0 + i as f64 * 110.0, y, b, filled);
+            }
+            y += DROP + 60.0;
+        }
+    }
+
+    /// make-ten page, three problems a row
+    pub fn render_make_ten(&mut self, target: &Surface, problems: &[MakeTen], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for chunk in problems.chunks(3) {
+            for (i, m) in chunk.iter().enumerate() {
+                draw_make_ten(&cr, 40.0 + i as f64 * 185.0, y, m, filled);
+            }
+            y += DROP + 40.0;
+        }
+    }
+}
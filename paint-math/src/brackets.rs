@@ -0,0 +1,53 @@
+use mathgen::brackets::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+
+/// problems drawn before giving up on finding a bracket puzzle
+const MAX_TRIES: usize = 10000;
+
+/// Turns the problems of another generator into bracket-insertion puzzles,
+/// skipping those that need no brackets or allow more than one placement.
+/// A template such as `_ ÷ (_ - _) x _` keeps the hit rate up.
+pub struct BracketPuzzleGen<G: MathGenerator> {
+    pub g: G,
+}
+
+impl<G: MathGenerator> BracketPuzzleGen<G> {
+    pub fn new(g: G) -> Self {
+        BracketPuzzleGen { g }
+    }
+
+    /// `None` when the generator never produced a bracket puzzle
+    pub fn puzzle(&mut self) -> Option<BracketPuzzle> {
+        (0..MAX_TRIES).find_map(|_| BracketPuzzle::new(&self.g.generate_rand_math()))
+    }
+}
+
+/// `48÷6-2x3=36` at (x, y), `filled` shows where the brackets go
+pub fn draw_bracket_puzzle(cr: &Context, x: f64, y: f64, p: &BracketPuzzle, filled: bool) {
+    let text = if filled {
+        format!("{}={}", p.answer, p.result)
+    } else {
+        p.to_string()
+    };
+    cr.move_to(x, y);
+    cr.show_text(&text);
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// bracket-insertion page, two puzzles a row
+    pub fn render_bracket_puzzles(&mut self, target: &Surface, puzzles: &[BracketPuzzle], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for chunk in puzzles.chunks(2) {
+            for (i, p) in chunk.iter().enumerate() {
+                draw_bracket_puzzle(&cr, 40.0 + i as f64 * 280.0, y, p, filled);
+            }
+            y += 45.0;
+        }
+    }
+}
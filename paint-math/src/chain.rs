@@ -0,0 +1,129 @@
+use mathgen::chain::*;
+use mathgen::enumerate::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+
+const BOX_WIDTH: f64 = 40.0;
+const BOX_HEIGHT: f64 = 28.0;
+const ARROW: f64 = 46.0;
+const LINE_GAP: f64 = 16.0;
+/// steps that fit across the page, a longer chain carries on below
+const STEPS_PER_LINE: usize = 6;
+/// starting numbers tried before giving up on the rules
+const MAX_TRIES: usize = 10000;
+
+/// Generates running-total chains within the ranges of `rules`, as
+/// `PyramidGen` does: the start comes from its operands, each step is
+/// `total op operand` with the operand from `operands_of(op, true)`, and
+/// every running total must be a result it accepts.
+pub struct ChainGen<R: Rules> {
+    pub rules: R,
+    pub length: usize,
+    pub ops: OpSet,
+
+    rng: ThreadRng,
+}
+
+impl<R: Rules> ChainGen<R> {
+    pub fn new(rules: R) -> Self {
+        ChainGen {
+            rules,
+            length: 5,
+            ops: OpSet::ALL,
+            rng: thread_rng(),
+        }
+    }
+
+    /// `None` when no chain kept its totals within the rules
+    pub fn chain(&mut self) -> Option<Chain> {
+        let start = self.rules.operands();
+        if start.start >= start.end || self.ops.is_empty() {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| {
+            let start = self.rng.gen_range(start.start, start.end);
+            Chain::random(&self.rules, start, self.length, self.ops, &mut self.rng)
+        })
+    }
+
+    pub fn chains(&mut self, n: usize) -> Option<Vec<Chain>> {
+        (0..n).map(|_| self.chain()).collect()
+    }
+}
+
+fn draw_box(cr: &Context, x: f64, y: f64, value: Option<i32>) {
+    cr.rectangle(x, y, BOX_WIDTH, BOX_HEIGHT);
+    cr.stroke();
+    if let Some(v) = value {
+        let s = v.to_string();
+        let ext = cr.text_extents(&s);
+        cr.move_to(
+            x + (BOX_WIDTH - ext.width) / 2.0 - ext.x_bearing,
+            y + (BOX_HEIGHT - ext.height) / 2.0 - ext.y_bearing,
+        );
+        cr.show_text(&s);
+    }
+}
+
+/// arrow from (x, y) to the right with the step written above it
+fn draw_arrow(cr: &Context, x: f64, y: f64, op: Op, n: i32) {
+    let end = x + ARROW - 6.0;
+    cr.move_to(x + 4.0, y);
+    cr.line_to(end, y);
+    cr.move_to(end - 6.0, y - 4.0);
+    cr.line_to(end, y);
+    cr.line_to(end - 6.0, y + 4.0);
+    cr.stroke();
+
+    cr.save();
+    cr.set_font_size(12.0);
+    let s = format!("{}{}", op, n);
+    let ext = cr.text_extents(&s);
+    cr.move_to(x + (ARROW - ext.x_advance) / 2.0, y - 6.0);
+    cr.show_text(&s);
+    cr.restore();
+}
+
+/// lines `draw_chain` takes for `chain`
+pub fn chain_lines(chain: &Chain) -> usize {
+    chain.steps.len().saturating_sub(1) / STEPS_PER_LINE + 1
+}
+
+/// the chain from (x, y): the start in the first box, then an arrow and a
+/// box to fill for each step, `filled` writes the running totals in. After
+/// `STEPS_PER_LINE` steps the chain carries on from the left on the next
+/// line.
+pub fn draw_chain(cr: &Context, x: f64, y: f64, chain: &Chain, filled: bool) {
+    cr.set_line_width(1.0);
+    let totals = chain.totals().unwrap_or_default();
+
+    draw_box(cr, x, y, Some(chain.start));
+    let (mut bx, mut by) = (x + BOX_WIDTH, y);
+    for (i, &(op, n)) in chain.steps.iter().enumerate() {
+        if i > 0 && i % STEPS_PER_LINE == 0 {
+            bx = x + BOX_WIDTH;
+            by += BOX_HEIGHT + LINE_GAP;
+        }
+        draw_arrow(cr, bx, by + BOX_HEIGHT / 2.0, op, n);
+        bx += ARROW;
+        draw_box(cr, bx, by, totals.get(i).copied().filter(|_| filled));
+        bx += BOX_WIDTH;
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// running-total page, one chain a row
+    pub fn render_chains(&mut self, target: &Surface, chains: &[Chain], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 80.0;
+        for c in chains {
+            draw_chain(&cr, 20.0, y, c, filled);
+            y += chain_lines(c) as f64 * (BOX_HEIGHT + LINE_GAP) + 16.0;
+        }
+    }
+}
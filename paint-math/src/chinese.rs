@@ -0,0 +1,102 @@
+use mathgen::chinese::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::ops::Range;
+
+/// Generates reading, writing and place-value problems on large numbers.
+/// `zero_share` of the digits after the first are zeros, to practise 零.
+pub struct NumberWordsGen {
+    pub digits: Range<usize>,
+    pub zero_share: f64,
+
+    rng: ThreadRng,
+}
+
+impl NumberWordsGen {
+    pub fn new() -> Self {
+        NumberWordsGen {
+            digits: 5..10,
+            zero_share: 0.3,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn number(&mut self) -> i64 {
+        let len = self.rng.gen_range(self.digits.start.max(1), self.digits.end.clamp(2, 17));
+        let mut n = self.rng.gen_range(1, 10);
+        for _ in 1..len {
+            let d = if self.rng.gen_bool(self.zero_share) {
+                0
+            } else {
+                self.rng.gen_range(1, 10)
+            };
+            n = n * 10 + d;
+        }
+        n
+    }
+
+    pub fn read(&mut self) -> NumberProblem {
+        NumberProblem::Read(self.number())
+    }
+
+    pub fn write(&mut self) -> NumberProblem {
+        NumberProblem::Write(self.number())
+    }
+
+    /// a nonzero digit that occurs only once in the number
+    pub fn place_value(&mut self) -> NumberProblem {
+        loop {
+            let n = self.number();
+            let len = n.to_string().len();
+            let unique = (0..len)
+                .filter(|&p| {
+                    let d = digit_at(n, p);
+                    d != 0 && (0..len).filter(|&q| digit_at(n, q) == d).count() == 1
+                })
+                .collect::<Vec<_>>();
+            if let Some(&place) = unique.choose(&mut self.rng) {
+                return NumberProblem::PlaceValue { n, place };
+            }
+        }
+    }
+
+    /// one of the three kinds at random
+    pub fn problem(&mut self) -> NumberProblem {
+        match self.rng.gen_range(0, 3) {
+            0 => self.read(),
+            1 => self.write(),
+            _ => self.place_value(),
+        }
+    }
+}
+
+impl Default for NumberWordsGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// reading and writing numbers page, one problem a row, `filled` writes
+    /// the answers in
+    pub fn render_number_words(&mut self, target: &Surface, problems: &[NumberProblem], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
+
+        let mut y = 90.0;
+        for (i, p) in problems.iter().enumerate() {
+            let line = match p {
+                NumberProblem::PlaceValue { .. } if filled => p.to_string().replace("( )", &format!("({})", p.answer())),
+                NumberProblem::PlaceValue { .. } => p.to_string().replace("( )", "(      )"),
+                _ if filled => format!("{}{}", p, p.answer()),
+                _ => format!("{}{}", p, "_".repeat(24)),
+            };
+            cr.move_to(30.0, y);
+            cr.show_text(&format!("{}. {}", i + 1, line));
+            y += 40.0;
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use mathgen::choice::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::f64::consts::PI;
+
+/// horizontal room for one option
+const OPTION_WIDTH: f64 = 62.0;
+
+/// `n` problems from `g` with `count` options each
+pub fn multiple_choice<G: MathGenerator>(g: &mut G, n: usize, count: usize) -> Vec<MultipleChoice> {
+    let mut rng = thread_rng();
+    (0..n)
+        .map(|_| MultipleChoice::new(&g.generate_rand_math(), count, &mut rng))
+        .collect()
+}
+
+/// problem at (x, y) with the lettered options below it, `filled` circles
+/// the letter of the correct one
+pub fn draw_multiple_choice(cr: &Context, x: f64, y: f64, mc: &MultipleChoice, filled: bool) {
+    cr.move_to(x, y);
+    cr.show_text(&format!("{}=(  )", mc.problem));
+
+    let y = y + 24.0;
+    cr.set_line_width(1.0);
+    for (i, o) in mc.options.iter().enumerate() {
+        let ox = x + i as f64 * OPTION_WIDTH;
+        let letter = MultipleChoice::letter(i).to_string();
+        cr.move_to(ox, y);
+        cr.show_text(&format!("{}.{}", letter, o));
+
+        if filled && mc.is_correct(i) {
+            let ext = cr.text_extents(&letter);
+            cr.new_path();
+            cr.arc(ox + ext.x_advance / 2.0, y - ext.height / 2.0, 9.0, 0.0, 2.0 * PI);
+            cr.stroke();
+        }
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// multiple-choice page, two problems a row
+    pub fn render_multiple_choice(&mut self, target: &Surface, problems: &[MultipleChoice], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for chunk in problems.chunks(2) {
+            for (i, mc) in chunk.iter().enumerate() {
+                draw_multiple_choice(&cr, 30.0 + i as f64 * 280.0, y, mc, filled);
+            }
+            y += 70.0;
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use mathgen::crossnumber::*;
+use mathgen::enumerate::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+
+const CELL: f64 = 40.0;
+/// lattices tried before giving up on the rules
+const MAX_TRIES: usize = 100000;
+
+/// Builds cross-number puzzles within the ranges of `rules`, as
+/// `PyramidGen` does: the corner numbers come from its operands and every
+/// equation must be a step it allows with a result it accepts.
+pub struct CrossNumberGen<R: Rules> {
+    pub rules: R,
+    pub ops: OpSet,
+    /// numbers blanked out of the nine
+    pub blanks: usize,
+
+    rng: ThreadRng,
+}
+
+impl<R: Rules> CrossNumberGen<R> {
+    pub fn new(rules: R) -> Self {
+        CrossNumberGen {
+            rules,
+            ops: OpSet::ALL,
+            blanks: 5,
+            rng: thread_rng(),
+        }
+    }
+
+    /// `None` when no lattice kept to the rules with that many blanks
+    pub fn puzzle(&mut self) -> Option<CrossNumber> {
+        if self.blanks > 9 || self.ops.is_empty() {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| CrossNumber::lattice(&self.rules, self.ops, self.blanks, &mut self.rng))
+    }
+
+    pub fn puzzles(&mut self, n: usize) -> Option<Vec<CrossNumber>> {
+        (0..n).map(|_| self.puzzle()).collect()
+    }
+}
+
+/// the grid with its top left corner at (x, y): a box for every number,
+/// operators and `=` between them, blanks left empty unless `filled`
+pub fn draw_crossnumber(cr: &Context, x: f64, y: f64, p: &CrossNumber, filled: bool) {
+    let grid = match p.grid() {
+        Some(g) => g,
+        None => return,
+    };
+
+    cr.set_line_width(1.0);
+    for (&(r, c), t) in &grid {
+        let (cx, cy) = (x + c as f64 * CELL, y + r as f64 * CELL);
+        let text = match t {
+            Token::Number(_) if p.blanks.contains(&(r, c)) && !filled => String::new(),
+            Token::Number(v) => v.to_string(),
+            Token::Op(op) => op.to_string(),
+            Token::Equals => "=".to_string(),
+        };
+        if let Token::Number(_) = t {
+            cr.rectangle(cx, cy, CELL, CELL);
+            cr.stroke();
+        }
+
+        let ext = cr.text_extents(&text);
+        cr.move_to(
+            cx + (CELL - ext.width) / 2.0 - ext.x_bearing,
+            cy + (CELL - ext.height) / 2.0 - ext.y_bearing,
+        );
+        cr.show_text(&text);
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// cross-number pages, two grids a row
+    pub fn render_crossnumbers(&mut self, target: &Surface, puzzles: &[CrossNumber], filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 70.0;
+        for chunk in puzzles.chunks(2) {
+            let height = chunk.iter().map(|p| p.size().0).max().unwrap_or(0) as f64 * CELL;
+            if y > 70.0 && y + height > PAGE_BOTTOM {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+                y = 70.0;
+            }
+            for (i, p) in chunk.iter().enumerate() {
+                draw_crossnumber(&cr, 50.0 + i as f64 * 270.0, y, p, filled);
+            }
+            y += height + 40.0;
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use mathgen::division::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use std::ops::Range;
+
+/// width of one digit column
+const CELL: f64 = 10.0;
+/// distance between two stacked rows
+const LINE: f64 = 18.0;
+
+fn show_number(cr: &Context, x: f64, y: f64, value: i32, column: usize) {
+    let s = value.to_string();
+    for (i, ch) in s.chars().enumerate() {
+        let c = column as f64 + 1.0 + i as f64 - s.len() as f64;
+        cr.move_to(x + c * CELL, y);
+        cr.show_text(&ch.to_string());
+    }
+}
+
+/// vertical space `draw_long_division` takes for `ld`
+pub fn long_division_height(ld: &LongDivision) -> f64 {
+    (ld.layout().len() + 2) as f64 * LINE
+}
+
+/// draw `ld` with the quotient baseline at `y`. When `filled` is false only
+/// the divisor, bracket and dividend are drawn and the space for the worked
+/// rows is left empty.
+pub fn draw_long_division(cr: &Context, x: f64, y: f64, ld: &LongDivision, filled: bool) {
+    let divisor = ld.divisor.to_string();
+    let bx = x + (divisor.len() as f64 + 0.5) * CELL;
+    let dx = bx + CELL * 0.5;
+    let right = dx + ld.columns() as f64 * CELL + CELL * 0.5;
+    let y1 = y + LINE + 4.0;
+
+    cr.move_to(x, y1);
+    cr.show_text(&divisor);
+    show_number(cr, dx, y1, ld.dividend, ld.columns() - 1);
+
+    // bracket: the bar over the dividend and the curve in front of it
+    cr.set_line_width(1.0);
+    cr.move_to(right, y + 5.0);
+    cr.line_to(bx, y + 5.0);
+    cr.line_to(bx + 3.0, y + 5.0 + LINE * 0.5);
+    cr.line_to(bx, y1 + 4.0);
+    cr.stroke();
+
+    if !filled {
+        return;
+    }
+
+    for (column, d) in ld.quotient_cells().iter().enumerate() {
+        if let Some(d) = d {
+            show_number(cr, dx, y, *d as i32, column);
+        }
+    }
+
+    let mut by = y1;
+    for row in ld.layout() {
+        by += LINE;
+        show_number(cr, dx, by, row.value, row.column);
+        if row.rule_below {
+            let len = row.value.to_string().len() as f64;
+            let end = dx + (row.column + 1) as f64 * CELL;
+            cr.move_to(end - (len + 0.5) * CELL, by + 5.0);
+            cr.line_to(end + CELL * 0.5, by + 5.0);
+            cr.stroke();
+        }
+    }
+}
+
+impl PrimitiveMathGen {
+    /// random long division, `exact` keeps only those without remainder
+    pub fn gen_long_division(&mut self, dividend: Range<i32>, divisor: Range<i32>, exact: bool) -> LongDivision {
+        loop {
+            let (a, b) = (self.rand(dividend.clone()), self.rand(divisor.clone()));
+            if b > 0 && a >= b && (!exact || a % b == 0) {
+                if let Some(ld) = LongDivision::new(a, b) {
+                    return ld;
+                }
+            }
+        }
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// long division page, three problems a row. `filled` renders the answer key.
+    pub fn render_long_division(&mut self, target: &Surface, problems: &[LongDivision], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 80.0;
+        for chunk in problems.chunks(3) {
+            for (i, ld) in chunk.iter().enumerate() {
+                draw_long_division(&cr, 40.0 + i as f64 * 180.0, y, ld, filled);
+            }
+            y += chunk.iter().map(long_division_height).fold(0.0, f64::max) + 20.0;
+        }
+    }
+}
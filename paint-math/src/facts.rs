@@ -0,0 +1,90 @@
+use mathgen::facts::*;
+use mathgen::math::*;
+
+use rand::prelude::*;
+use std::collections::BTreeMap;
+
+/// Deals multiplication facts so that every fact comes up `repeat` times
+/// per round, in shuffled order, instead of picking operands at random.
+pub struct FactDrillGen {
+    facts: Vec<Fact>,
+    repeat: usize,
+    /// also drill `a*b ÷ b` for every fact
+    pub inverse: bool,
+
+    deck: Vec<Expr>,
+    dealt: Vec<Expr>,
+    rng: ThreadRng,
+}
+
+impl FactDrillGen {
+    /// panics when `facts` is empty, a round would have nothing to deal
+    pub fn new(facts: Vec<Fact>) -> Self {
+        assert!(!facts.is_empty(), "fact set must not be empty");
+        FactDrillGen {
+            facts,
+            repeat: 1,
+            inverse: false,
+            deck: Vec::new(),
+            dealt: Vec::new(),
+            rng: thread_rng(),
+        }
+    }
+
+    /// every fact `repeat` times per round, panics when `repeat` is 0
+    pub fn with_repeat(mut self, repeat: usize) -> Self {
+        assert!(repeat > 0, "every fact must come up at least once per round");
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn facts(&self) -> &[Fact] {
+        &self.facts
+    }
+
+    /// one full round as a sheet, shuffled
+    pub fn sheet(&mut self) -> Vec<Expr> {
+        let mut problems = round(&self.facts, self.repeat, self.inverse);
+        problems.shuffle(&mut self.rng);
+        self.dealt.extend(problems.iter().cloned());
+        problems
+    }
+
+    /// facts dealt so far and how often
+    pub fn coverage(&self) -> BTreeMap<Fact, usize> {
+        coverage(&self.dealt)
+    }
+
+    /// facts of the set that were not dealt yet
+    pub fn missing(&self) -> Vec<Fact> {
+        let cov = self.coverage();
+        self.facts.iter().filter(|f| !cov.contains_key(f)).cloned().collect()
+    }
+
+    /// forget what was dealt, the next problem starts a fresh round
+    pub fn reset(&mut self) {
+        self.deck.clear();
+        self.dealt.clear();
+    }
+}
+
+impl MathGenerator for FactDrillGen {
+    /// next problem of the current round, a new round is shuffled when
+    /// the previous one runs out
+    fn generate_rand_math(&mut self) -> Expr {
+        if self.deck.is_empty() {
+            self.deck = round(&self.facts, self.repeat, self.inverse);
+            self.deck.shuffle(&mut self.rng);
+        }
+
+        // `new` and `with_repeat` keep every round from being empty
+        let e = self.deck.pop().unwrap();
+        self.dealt.push(e.clone());
+        e
+    }
+
+    /// facts have a fixed shape, `noprand` and `nop` are ignored
+    fn gen(&mut self, _noprand: i32, _nop: i32) -> Expr {
+        self.generate_rand_math()
+    }
+}
@@ -0,0 +1,131 @@
+use mathgen::kenken::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+
+const CELL: f64 = 50.0;
+/// grids cut before giving up on a unique one
+const MAX_TRIES: usize = 10000;
+
+/// Generates cage puzzles on `size` x `size` grids with cages of up to
+/// `max_cage` cells and operators from `ops`.
+pub struct KenKenGen {
+    pub size: usize,
+    pub max_cage: usize,
+    pub ops: OpSet,
+
+    rng: ThreadRng,
+}
+
+impl KenKenGen {
+    pub fn new() -> Self {
+        KenKenGen {
+            size: 4,
+            max_cage: 3,
+            ops: OpSet::ALL,
+            rng: thread_rng(),
+        }
+    }
+
+    /// `None` when no grid with a unique solution turned up
+    pub fn puzzle(&mut self) -> Option<KenKen> {
+        if self.size == 0 || self.max_cage == 0 || self.ops.is_empty() {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| KenKen::random(self.size, self.max_cage, self.ops, &mut self.rng))
+    }
+
+    pub fn puzzles(&mut self, n: usize) -> Option<Vec<KenKen>> {
+        (0..n).map(|_| self.puzzle()).collect()
+    }
+}
+
+impl Default for KenKenGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// the grid with its top left corner at (x, y): thin lines between cells,
+/// thick ones around cages, each cage's label in its first cell, `filled`
+/// writes the solution in
+pub fn draw_kenken(cr: &Context, x: f64, y: f64, p: &KenKen, filled: bool) {
+    let n = p.size;
+    let mut owner = vec![vec![0; n]; n];
+    for (i, cage) in p.cages.iter().enumerate() {
+        for &(r, c) in &cage.cells {
+            owner[r][c] = i;
+        }
+    }
+
+    // the top and left edge of every cell, thick where the cage changes,
+    // and the outer edges at the bottom and right
+    let edge = |x0: f64, y0: f64, dx: f64, dy: f64, thick: bool| {
+        cr.set_line_width(if thick { 3.0 } else { 0.5 });
+        cr.move_to(x0, y0);
+        cr.rel_line_to(dx, dy);
+        cr.stroke();
+    };
+    for (r, row) in owner.iter().enumerate() {
+        for (c, &o) in row.iter().enumerate() {
+            let (cx, cy) = (x + c as f64 * CELL, y + r as f64 * CELL);
+            edge(cx, cy, CELL, 0.0, r == 0 || owner[r - 1][c] != o);
+            edge(cx, cy, 0.0, CELL, c == 0 || row[c - 1] != o);
+            if r + 1 == n {
+                edge(cx, cy + CELL, CELL, 0.0, true);
+            }
+            if c + 1 == n {
+                edge(cx + CELL, cy, 0.0, CELL, true);
+            }
+        }
+    }
+
+    cr.save();
+    cr.set_font_size(11.0);
+    for cage in &p.cages {
+        let (r, c) = cage.cells[0];
+        cr.move_to(x + c as f64 * CELL + 4.0, y + r as f64 * CELL + 13.0);
+        cr.show_text(&cage.label());
+    }
+    cr.restore();
+
+    if filled {
+        cr.save();
+        cr.set_font_size(22.0);
+        for (r, row) in p.solution.iter().enumerate() {
+            for (c, v) in row.iter().enumerate() {
+                let s = v.to_string();
+                let ext = cr.text_extents(&s);
+                cr.move_to(
+                    x + c as f64 * CELL + (CELL - ext.width) / 2.0 - ext.x_bearing,
+                    y + r as f64 * CELL + (CELL - ext.height) / 2.0 - ext.y_bearing + 5.0,
+                );
+                cr.show_text(&s);
+            }
+        }
+        cr.restore();
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// cage puzzle pages, grids centred one under the other, `filled`
+    /// gives the solution pages
+    pub fn render_kenken(&mut self, target: &Surface, puzzles: &[KenKen], filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 70.0;
+        for p in puzzles {
+            let side = p.size as f64 * CELL;
+            if y > 70.0 && y + side > PAGE_BOTTOM {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+                y = 70.0;
+            }
+            draw_kenken(&cr, (8.3 * 72.0 - side) / 2.0, y, p, filled);
+            y += side + 40.0;
+        }
+    }
+}
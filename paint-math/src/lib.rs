@@ -1 +1,21 @@
 pub mod paint;
+pub mod division;
+pub mod bond;
+pub mod facts;
+pub mod shortcut;
+pub mod uniform;
+pub mod points;
+pub mod operators;
+pub mod brackets;
+pub mod choice;
+pub mod sequence;
+pub mod percent;
+pub mod chinese;
+pub mod spoken;
+pub mod chain;
+pub mod anzan;
+pub mod kenken;
+pub mod pyramid;
+pub mod crossnumber;
+pub mod magic;
+pub mod section;
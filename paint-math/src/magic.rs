@@ -0,0 +1,179 @@
+use mathgen::magic::*;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::ops::Range;
+
+const CELL: f64 = 40.0;
+/// grids tried before giving up on the givens asked for
+const MAX_TRIES: usize = 1000;
+
+/// Generates magic squares or sum grids of `size` x `size` with `givens`
+/// cells filled in. A sum grid draws its numbers from `numbers`; a magic
+/// square uses `size * size` numbers of `numbers` with an equal step
+/// between them, such as 1 to 9 or 5, 7, ..., 35. A larger square needs
+/// a wider `numbers` than the default 1 to 9, such as 1 to 16 on a 4 x 4.
+pub struct GridPuzzleGen {
+    pub kind: GridKind,
+    pub size: usize,
+    pub numbers: Range<i32>,
+    pub givens: usize,
+
+    rng: ThreadRng,
+}
+
+impl GridPuzzleGen {
+    pub fn new(kind: GridKind) -> Self {
+        GridPuzzleGen {
+            kind,
+            size: 3,
+            numbers: 1..10,
+            givens: 3,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn rand(&mut self, r: Range<i32>) -> i32 {
+        self.rng.gen_range(r.start, r.end)
+    }
+
+    /// the numbers of a magic square: `size * size` of them in `numbers`
+    /// with a random start and step, `None` when `numbers` is too short
+    fn progression(&mut self) -> Option<Vec<i32>> {
+        let count = (self.size * self.size) as i32;
+        let span = self.numbers.end - self.numbers.start - 1;
+        if count < 2 || span < count - 1 {
+            return None;
+        }
+        let step = self.rand(1..span / (count - 1) + 1);
+        let start = self.rand(self.numbers.start..self.numbers.end - step * (count - 1));
+        Some((0..count).map(|k| start + step * k).collect())
+    }
+
+    /// `None` when no grid had a unique solution from that many givens
+    pub fn puzzle(&mut self) -> Option<GridPuzzle> {
+        if self.size == 0 || self.givens > self.size * self.size || self.numbers.is_empty() {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| {
+            let grid = match self.kind {
+                GridKind::Magic => {
+                    let values = self.progression()?;
+                    GridPuzzle::magic(self.size, &values, &mut self.rng)
+                }
+                GridKind::Sums => {
+                    let values = self.numbers.clone().collect::<Vec<_>>();
+                    GridPuzzle::sum_grid(self.size, &values, &mut self.rng)
+                }
+            };
+            grid?.reveal(self.givens, &mut self.rng)
+        })
+    }
+
+    pub fn puzzles(&mut self, n: usize) -> Option<Vec<GridPuzzle>> {
+        (0..n).map(|_| self.puzzle()).collect()
+    }
+}
+
+fn centred(cr: &Context, x: f64, y: f64, s: &str) {
+    let ext = cr.text_extents(s);
+    cr.move_to(
+        x + (CELL - ext.width) / 2.0 - ext.x_bearing,
+        y + (CELL - ext.height) / 2.0 - ext.y_bearing,
+    );
+    cr.show_text(s);
+}
+
+/// the grid with its top left corner at (x, y), givens printed and blanks
+/// left empty unless `filled`. A sum grid has its row sums on the right
+/// and its column sums below; a magic square has the numbers to use and
+/// the sum below it.
+pub fn draw_grid_puzzle(cr: &Context, x: f64, y: f64, p: &GridPuzzle, filled: bool) {
+    let n = p.size();
+    cr.set_line_width(1.0);
+    for (r, row) in p.cells.iter().enumerate() {
+        for (c, v) in row.iter().enumerate() {
+            let (cx, cy) = (x + c as f64 * CELL, y + r as f64 * CELL);
+            cr.rectangle(cx, cy, CELL, CELL);
+            cr.stroke();
+            if p.given[r][c] || filled {
+                centred(cr, cx, cy, &v.to_string());
+            }
+        }
+    }
+
+    let sums = p.sums();
+    let side = n as f64 * CELL;
+    match p.kind {
+        GridKind::Sums => {
+            for i in 0..n {
+                centred(cr, x + side, y + i as f64 * CELL, &format!("={}", sums[i]));
+                centred(cr, x + i as f64 * CELL, y + side, &sums[n + i].to_string());
+            }
+        }
+        GridKind::Magic => {
+            let v = &p.values;
+            let numbers = match v.len() {
+                0..=9 => v.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","),
+                _ => format!("{},{},…,{}", v[0], v[1], v[v.len() - 1]),
+            };
+            cr.save();
+            cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
+            cr.set_font_size(10.0);
+            cr.move_to(x, y + side + 16.0);
+            cr.show_text(&format!("用 {} 各一次", numbers));
+            cr.move_to(x, y + side + 30.0);
+            cr.show_text(&format!("每行、每列、对角线的和都是 {}", sums[0]));
+            cr.restore();
+        }
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// magic square or sum grid pages, two grids a row
+    pub fn render_grid_puzzles(&mut self, target: &Surface, puzzles: &[GridPuzzle], filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 70.0;
+        for chunk in puzzles.chunks(2) {
+            let height = (chunk.iter().map(|p| p.size()).max().unwrap_or(0) + 1) as f64 * CELL;
+            if y > 70.0 && y + height > PAGE_BOTTOM {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+                y = 70.0;
+            }
+            for (i, p) in chunk.iter().enumerate() {
+                draw_grid_puzzle(&cr, 60.0 + i as f64 * 260.0, y, p, filled);
+            }
+            y += height + 30.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_squares_keep_to_the_range() {
+        let mut g = GridPuzzleGen::new(GridKind::Magic);
+        g.numbers = 1..30;
+        for p in g.puzzles(3).unwrap() {
+            assert!(p.values.iter().all(|v| (1..30).contains(v)));
+            assert_eq!(p.givens(), 3);
+            assert!(p.unique(), "{}", p);
+        }
+
+        // 1 to 9 is too few for a 4 x 4, 1 to 16 just fits
+        let mut g = GridPuzzleGen::new(GridKind::Magic);
+        g.size = 4;
+        g.givens = 8;
+        assert!(g.puzzle().is_none());
+        g.numbers = 1..17;
+        let p = g.puzzle().unwrap();
+        assert_eq!(p.values, (1..17).collect::<Vec<_>>());
+    }
+}
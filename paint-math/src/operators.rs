@@ -0,0 +1,163 @@
+use mathgen::math::Expr::*;
+use mathgen::math::*;
+use mathgen::operators::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+const RADIUS: f64 = 9.0;
+/// puzzles tried before giving up on the solution count
+const MAX_TRIES: usize = 10000;
+
+/// Generates fill-in-the-operator puzzles whose number of answers is within
+/// `solutions`, exactly one by default.
+pub struct OperatorPuzzleGen {
+    /// numbers in a puzzle
+    pub count: usize,
+    pub single_range: Range<i32>,
+    pub ops: OpSet,
+    pub brackets: bool,
+    pub solutions: Range<usize>,
+
+    rng: ThreadRng,
+}
+
+impl OperatorPuzzleGen {
+    pub fn new() -> Self {
+        OperatorPuzzleGen {
+            count: 3,
+            single_range: 1..10,
+            ops: OpSet::ALL,
+            brackets: false,
+            solutions: 1..2,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn rand(&mut self, r: Range<i32>) -> i32 {
+        self.rng.gen_range(r.start, r.end)
+    }
+
+    fn rand_op(&mut self) -> Op {
+        let ops = self.ops.iter().collect::<Vec<_>>();
+        *ops.choose(&mut self.rng).expect("at least one operator")
+    }
+
+    /// random grouping of `numbers` in order
+    fn rand_tree(&mut self, numbers: &[i32]) -> Expr {
+        if numbers.len() == 1 {
+            return Single(numbers[0]);
+        }
+        let i = self.rand(1..numbers.len() as i32) as usize;
+        let op = self.rand_op();
+        let (l, r) = (self.rand_tree(&numbers[..i]), self.rand_tree(&numbers[i..]));
+        Expr::join(op, l, r)
+    }
+
+    /// `None` when no puzzle had a solution count in `solutions`
+    pub fn puzzle(&mut self) -> Option<OperatorPuzzle> {
+        if self.count == 0 || self.single_range.start >= self.single_range.end || self.ops.is_empty() {
+            return None;
+        }
+        for _ in 0..MAX_TRIES {
+            let numbers = (0..self.count)
+                .map(|_| self.rand(self.single_range.clone()))
+                .collect::<Vec<_>>();
+            let e = if self.brackets {
+                self.rand_tree(&numbers)
+            } else {
+                let ops = (1..self.count).map(|_| self.rand_op()).collect::<Vec<_>>();
+                chain(&numbers, &ops)
+            };
+            let result = match e.checked_eval() {
+                Some(v) if v >= 0 => v,
+                _ => continue,
+            };
+
+            let p = OperatorPuzzle {
+                numbers,
+                result,
+                ops: self.ops,
+                brackets: self.brackets,
+            };
+            if self.solutions.contains(&p.solutions().len()) {
+                return Some(p);
+            }
+        }
+        None
+    }
+}
+
+impl Default for OperatorPuzzleGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_op(c: char) -> bool {
+    c == '+' || c == '-' || c == 'x' || c == '÷'
+}
+
+/// circle standing in for an operator, `op` written inside when given
+fn draw_circle(cr: &Context, x: f64, y: f64, op: Option<char>) -> f64 {
+    let (cx, cy) = (x + RADIUS + 2.0, y - RADIUS + 3.0);
+    cr.new_path();
+    cr.arc(cx, cy, RADIUS, 0.0, 2.0 * PI);
+    cr.stroke();
+
+    if let Some(c) = op {
+        let s = c.to_string();
+        let ext = cr.text_extents(&s);
+        cr.move_to(cx - ext.width / 2.0 - ext.x_bearing, cy - ext.height / 2.0 - ext.y_bearing);
+        cr.show_text(&s);
+    }
+    2.0 * RADIUS + 4.0
+}
+
+/// `3○4○5=17` with its baseline at (x, y), `filled` puts the first answer
+/// in, brackets included
+pub fn draw_operator_puzzle(cr: &Context, x: f64, y: f64, p: &OperatorPuzzle, filled: bool) {
+    let text = match p.solutions().first() {
+        Some(e) if filled => format!("{}={}", e, p.result),
+        // any operator will do, only its circle is drawn
+        _ => p.to_string().replace('○', "+"),
+    };
+
+    cr.set_line_width(1.0);
+    let mut x = x;
+    let mut run = String::new();
+    let flush = |run: &mut String, x: &mut f64| {
+        cr.move_to(*x, y);
+        cr.show_text(run);
+        *x += cr.text_extents(run).x_advance;
+        run.clear();
+    };
+    for c in text.chars() {
+        if is_op(c) {
+            flush(&mut run, &mut x);
+            x += draw_circle(cr, x, y, if filled { Some(c) } else { None });
+        } else {
+            run.push(c);
+        }
+    }
+    flush(&mut run, &mut x);
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// fill-in-the-operator page, two puzzles a row
+    pub fn render_operator_puzzles(&mut self, target: &Surface, puzzles: &[OperatorPuzzle], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for chunk in puzzles.chunks(2) {
+            for (i, p) in chunk.iter().enumerate() {
+                draw_operator_puzzle(&cr, 40.0 + i as f64 * 280.0, y, p, filled);
+            }
+            y += 50.0;
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use mathgen::math::*;
 use mathgen::math::Expr::*;
+use mathgen::enumerate::*;
+use mathgen::weights::*;
+use mathgen::template::*;
 
 use cairo::*;
 use log::*;
@@ -8,14 +11,28 @@ use std::ops::{Range, Bound, RangeBounds};
 use rand::prelude::*;
 use std::fmt::Debug;
 
+/// lowest a row of drawings may reach on an A4 page before it goes on the
+/// next one
+pub const PAGE_BOTTOM: f64 = 11.7 * 72.0 - 30.0;
+/// draws for one problem or subtree before giving up on the ranges
+const MAX_TRIES: usize = 10_000;
+/// divisors `PrimitiveMathGen` allows
+const DIVISORS: Range<i32> = 2..10;
+
 pub struct PrimitiveMathGen {
     pub level: i32,
     pub result_range: Range<i32>,
     pub single_range: Range<i32>,
     pub addition_range: Range<i32>,
     pub multiplication_range: Range<i32>,
+    pub ops: OpPlan,
+    /// fill this shape instead of a random one of `level`; its operators
+    /// come from the template, `ops` does not weigh them. When the ranges
+    /// leave no way to fill it the random shape is used after all.
+    pub template: Option<Template>,
 
     rng: ThreadRng,
+    stats: OpStats,
     // has_mul: bool, // Removed
     // has_div: bool, // Removed
 }
@@ -28,8 +45,14 @@ pub struct GenerativeMathGen {
     pub mul_range: Range<i32>,
     pub minus_range: Range<i32>,
     pub div_range: Range<i32>,
+    pub ops: OpPlan,
+    /// fill this shape instead of a random one of `level`; its operators
+    /// come from the template, `ops` does not weigh them. When the ranges
+    /// leave no way to fill it the random shape is used after all.
+    pub template: Option<Template>,
 
     rng: ThreadRng,
+    stats: OpStats,
     // has_mul: bool, // Removed
     // has_div: bool, // Removed
 }
@@ -69,97 +92,110 @@ impl MathGenerator for PrimitiveMathGen {
     /// level: 1 => two oprands one op
     /// level: 2 => three oprands two op
     /// level: 3 => four oprands three op
+    /// panics when `ops` leaves no room for the `x` or `÷` every problem needs,
+    /// or when the ranges leave no problems for the operators picked
     fn generate_rand_math(&mut self) -> Expr {
+        if let Some(e) = self.fill_template() {
+            return e
+        }
+
         let level = self.level;
+        assert!(
+            self.ops.can_pick(Op::Mul, level as usize) || self.ops.can_pick(Op::Div, level as usize),
+            "every problem needs x or ÷, but the operator weights rule both out"
+        );
         let (noprand, nop) = (level + 1, level);
-        loop {
+        for _ in 0..MAX_TRIES {
             let mut current_has_div = false;
             let mut current_has_mul = false;
-            let e = self.gen_expr_with_state(noprand, nop, &mut current_has_div, &mut current_has_mul);
+            let e = self.gen_expr_with_state(noprand, nop, 0, &mut current_has_div, &mut current_has_mul);
             //eprintln!("{:?} => {}", e, e);
-            if self.result_range.contains(&e.eval()) && 
-                (current_has_div || current_has_mul) { // Check the local state
+            // the flags survive retried subtrees, so check the operators actually used
+            if self.accepts(e.eval(), e.ops()) {
+                self.stats.record(&e);
                 return e
             }
         }
+        panic!("no problem with x or ÷ in {:?} fits the ranges and operators", self.result_range)
     }
 
     // Renamed original gen to gen_expr_with_state to pass down div/mul state
     fn gen(&mut self, noprand: i32, nop: i32) -> Expr {
         let mut current_has_div = false; // Dummy state, not used by top-level call
         let mut current_has_mul = false; // Dummy state, not used by top-level call
-        self.gen_expr_with_state(noprand, nop, &mut current_has_div, &mut current_has_mul)
+        let e = self.gen_expr_with_state(noprand, nop, 0, &mut current_has_div, &mut current_has_mul);
+        self.stats.record(&e);
+        e
+    }
+}
+
+/// the checks `generate_rand_math` applies, so the same problems can be enumerated
+impl Rules for PrimitiveMathGen {
+    fn operands(&self) -> Range<i32> {
+        self.single_range.clone()
+    }
+
+    fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+        match op {
+            Op::Div => DIVISORS.contains(&r) && (l / r < 10) && (l % r == 0),
+            Op::Mul => self.multiplication_range.contains(&l) && self.multiplication_range.contains(&r),
+            Op::Minus => self.addition_range.contains(&l) && self.addition_range.contains(&r) && l > r,
+            _ => true,
+        }
+    }
+
+    fn result(&self, value: i32) -> bool {
+        self.result_range.contains(&value)
+    }
+
+    fn accepts(&self, value: i32, ops: OpSet) -> bool {
+        self.result(value) && (ops.contains(Op::Div) || ops.contains(Op::Mul))
     }
 }
 
 impl PrimitiveMathGen {
     // Helper function to pass down the div/mul state
-    fn gen_expr_with_state(&mut self, noprand: i32, nop: i32, current_has_div: &mut bool, current_has_mul: &mut bool) -> Expr {
+    // depth: distance from the root, picks the operator weights
+    fn gen_expr_with_state(&mut self, noprand: i32, nop: i32, depth: usize, current_has_div: &mut bool, current_has_mul: &mut bool) -> Expr {
         match (noprand, nop) {
             (1, 0) => Single(self.rand(self.single_range.clone())),
             (2, 1) => {
-                loop {
-                    let op = self.rand_op();
+                for _ in 0..MAX_TRIES {
+                    let op = self.rand_op_at(depth);
+                    // a divisor from outside DIVISORS would never be allowed
+                    let divisors = if op == Op::Div { DIVISORS } else { self.single_range.clone() };
                     let (l, r_val) = (self.rand(self.single_range.clone()),
-                    self.rand(self.single_range.clone()));
+                    self.rand(divisors));
 
                     let e = Primitive(op, l, r_val);
-                    let mut op_has_div = false;
-                    let mut op_has_mul = false;
-                    if ! match op {
-                        Op::Div => {
-                            op_has_div = true;
-                            (2..10).contains(&r_val) && (l / r_val < 10) && (l % r_val == 0)
-                        },
-                        Op::Mul => {
-                            op_has_mul = true;
-                            self.multiplication_range.contains(&l) && self.multiplication_range.contains(&r_val)
-                        },
-                        Op::Minus => {
-                            self.addition_range.contains(&l) && self.addition_range.contains(&r_val) && l > r_val
-                        },
-                        _ =>  true
-                    } {
+                    if !self.allows(op, l, r_val) {
                         continue;
                     }
-                    *current_has_div |= op_has_div;
-                    *current_has_mul |= op_has_mul;
+                    *current_has_div |= op == Op::Div;
+                    *current_has_mul |= op == Op::Mul;
 
                     return e
                 }
+                panic!("no operands in {:?} fit the operators picked", self.single_range)
             }
             _ => {
-                let lnoprand = self.rand(1..noprand); // This uses Range<i32> which is fine for rand()
-                let rnoprand = noprand - lnoprand;
-
-                loop {
-                    let lhs = self.gen_expr_with_state(lnoprand, lnoprand - 1, current_has_div, current_has_mul);
-                    let rhs = self.gen_expr_with_state(rnoprand, rnoprand - 1, current_has_div, current_has_mul);
-
-                    let op = self.rand_op();
+                for _ in 0..MAX_TRIES {
+                    // a new split each time, some never fit the operators picked
+                    let lnoprand = self.rand(1..noprand); // This uses Range<i32> which is fine for rand()
+                    let rnoprand = noprand - lnoprand;
+                    let lhs = self.gen_expr_with_state(lnoprand, lnoprand - 1, depth + 1, current_has_div, current_has_mul);
+                    let rhs = self.gen_expr_with_state(rnoprand, rnoprand - 1, depth + 1, current_has_div, current_has_mul);
+
+                    let op = self.rand_op_at(depth);
                     let (l_eval, r_eval) = (lhs.eval(), rhs.eval());
-                    let mut op_has_div = false;
-                    let mut op_has_mul = false;
-                    if ! match op {
-                        Op::Div => {
-                            op_has_div = true;
-                            (2..10).contains(&r_eval) && (l_eval / r_eval < 10) && (l_eval % r_eval == 0)
-                        },
-                        Op::Mul => {
-                            op_has_mul = true;
-                            self.multiplication_range.contains(&l_eval) && self.multiplication_range.contains(&r_eval)
-                        },
-                        Op::Minus => {
-                            self.addition_range.contains(&l_eval) && self.addition_range.contains(&r_eval) && l_eval > r_eval
-                        },
-                        _ =>  true
-                    } {
+                    if !self.allows(op, l_eval, r_eval) {
                         continue;
                     }
-                    *current_has_div |= op_has_div;
-                    *current_has_mul |= op_has_mul;
+                    *current_has_div |= op == Op::Div;
+                    *current_has_mul |= op == Op::Mul;
                     return Compound(op, Box::new(lhs), Box::new(rhs));                
                 }
+                panic!("no subtrees of {:?} fit the operators picked", self.single_range)
             }
         }
     }
@@ -171,7 +207,10 @@ impl PrimitiveMathGen {
             result_range: 10..400,
             addition_range: 20..100,
             multiplication_range: 5..21,
+            ops: OpPlan::default(),
+            template: None,
             rng: thread_rng(),
+            stats: OpStats::default(),
             // has_mul: false, // Removed
             // has_div: false  // Removed
         }
@@ -182,20 +221,74 @@ impl PrimitiveMathGen {
     }
 
 
+    /// operator with the default weights of `ops`
     pub fn rand_op(&mut self) -> Op {
-        match self.rng.gen_range(0, 4) { 
-            3 => Op::Add,
-            1 => Op::Minus,
-            2 => Op::Mul,
-            0 => Op::Div,
-            _ => unreachable!(),
+        self.ops.default.pick(&mut self.rng)
+    }
+
+    /// operator for a node `depth` levels below the root
+    pub fn rand_op_at(&mut self, depth: usize) -> Op {
+        self.ops.pick(&mut self.rng, depth)
+    }
+
+    /// operator mix of the problems returned so far
+    pub fn op_stats(&self) -> &OpStats {
+        &self.stats
+    }
+
+    /// fill `template` if one is set, `None` when the ranges rule it out
+    fn fill_template(&mut self) -> Option<Expr> {
+        let t = self.template.clone()?;
+        let mut rng = self.rng.clone();
+        match t.fill(self, &mut rng) {
+            Some(e) => {
+                self.stats.record(&e);
+                Some(e)
+            }
+            None => {
+                warn!("template `{}` cannot be filled within the ranges, using a random shape", t);
+                None
+            }
         }
     }
 }
 
 
+/// the bounds `gen_iter` aims for, checked after the fact
+impl Rules for GenerativeMathGen {
+    fn operands(&self) -> Range<i32> {
+        self.single_range.clone()
+    }
+
+    /// divisors and right-hand factors come from fixed ranges, as in `gen_iter`
+    fn operands_of(&self, op: Op, right: bool) -> Range<i32> {
+        match (op, right) {
+            (Op::Div, true) => 2..10,
+            (Op::Mul, true) => 5..20,
+            _ => self.single_range.clone(),
+        }
+    }
+
+    fn allows(&self, op: Op, l: i32, r: i32) -> bool {
+        match op {
+            Op::Div => (2..10).contains(&r) && l % r == 0 && self.div_range.contains(&(l / r)),
+            Op::Mul => (5..20).contains(&r) && matches!(l.checked_mul(r), Some(v) if self.mul_range.contains(&v)),
+            Op::Minus => self.minus_range.contains(&(l - r)),
+            Op::Add => self.add_range.contains(&(l + r)),
+        }
+    }
+
+    fn result(&self, value: i32) -> bool {
+        self.result_range.contains(&value)
+    }
+}
+
 impl MathGenerator for GenerativeMathGen {
     fn generate_rand_math(&mut self) -> Expr {
+        if let Some(e) = self.fill_template() {
+            return e
+        }
+
         let level = self.level;
         self.gen(level+1, level)
     }
@@ -205,6 +298,7 @@ impl MathGenerator for GenerativeMathGen {
         // The logic relies on gen_iter which doesn't use those fields.
         loop {
             if let Some(e) = self.gen_iter(noprand, nop, self.result_range.clone()) {
+                self.stats.record(&e);
                 return e
             }
         }
@@ -229,13 +323,21 @@ impl GenerativeMathGen {
             minus_range: 20..100,
             mul_range: 11..200,
             div_range: 5..11,
+            ops: OpPlan::default(),
+            template: None,
             rng: thread_rng(),
+            stats: OpStats::default(),
             // has_mul: false, // Removed
             // has_div: false  // Removed
         }
     }
 
     pub fn gen_iter<T: RangeBounds<i32> + Clone + Debug>(&mut self, noprand: i32, nop: i32, bound: T) -> Option<Expr> {
+        self.gen_iter_at(noprand, nop, bound, 0)
+    }
+
+    // depth: distance from the root, picks the operator weights
+    fn gen_iter_at<T: RangeBounds<i32> + Clone + Debug>(&mut self, noprand: i32, nop: i32, bound: T, depth: usize) -> Option<Expr> {
         match (noprand, nop) {
             (1, 0) => {
                 range_union(self.single_range.clone(), bound.clone())
@@ -243,7 +345,7 @@ impl GenerativeMathGen {
             }
             (2, 1) => {
                 let (mut l, mut r_val) = (0, 0); // Renamed r to r_val to avoid conflict
-                let op = self.rand_op();
+                let op = self.rand_op_at(depth);
                 match op {
                     Op::Div => {
                         let range = try_option!(range_union(bound.clone(), 2..10));
@@ -293,13 +395,13 @@ impl GenerativeMathGen {
                 let mut lhs: Expr; // Declare lhs as mutable
                 let mut rhs: Expr; // Declare rhs as mutable
                 let (mut l_eval, mut r_eval) = (0, 0); // Renamed l,r to l_eval, r_eval
-                let op = self.rand_op();
+                let op = self.rand_op_at(depth);
                 match op {
                     Op::Div => {
                         let mut retries = 10;
                         loop {
                             let range_rhs = try_option!(range_union(bound.clone(), 2..10)); // Renamed range to range_rhs
-                            rhs = match self.gen_iter(rnoprand, rnoprand-1, range_rhs) {
+                            rhs = match self.gen_iter_at(rnoprand, rnoprand-1, range_rhs, depth + 1) {
                                 Some(v) => v,
                                 None => return None,
                             };
@@ -316,7 +418,7 @@ impl GenerativeMathGen {
                         }
                         let range_lhs = (self.div_range.start*r_eval)..(self.div_range.end*r_eval); // Renamed range to range_lhs
                         let range_lhs_final = try_option!(range_union(bound.clone(), range_lhs)); // Renamed range to range_lhs_final
-                        lhs = match self.gen_iter(lnoprand, lnoprand-1, range_lhs_final) {
+                        lhs = match self.gen_iter_at(lnoprand, lnoprand-1, range_lhs_final, depth + 1) {
                             Some(v) => v,
                             None => return None,
                         };
@@ -325,7 +427,7 @@ impl GenerativeMathGen {
                         let mut retries = 10;
                         loop {
                             let range_lhs = try_option!(range_union(bound.clone(), 5..20)); // Renamed range to range_lhs
-                            lhs = match self.gen_iter(lnoprand, lnoprand-1, range_lhs) {
+                            lhs = match self.gen_iter_at(lnoprand, lnoprand-1, range_lhs, depth + 1) {
                                 Some(v) => v,
                                 None => return None,
                             };
@@ -337,7 +439,7 @@ impl GenerativeMathGen {
                                 continue;
                             }
                             let range_rhs = (self.mul_range.start/l_eval)..(self.mul_range.end/l_eval); // Renamed range to range_rhs
-                            rhs = match self.gen_iter(rnoprand, rnoprand-1, range_rhs) {
+                            rhs = match self.gen_iter_at(rnoprand, rnoprand-1, range_rhs, depth + 1) {
                                 Some(v) => v,
                                 None => return None,
                             };
@@ -356,28 +458,28 @@ impl GenerativeMathGen {
                     },
                     Op::Minus => {
                         let range_rhs = try_option!(range_union(bound.clone(), self.single_range.clone())); // Renamed range to range_rhs
-                        rhs = match self.gen_iter(rnoprand, rnoprand-1, range_rhs) {
+                        rhs = match self.gen_iter_at(rnoprand, rnoprand-1, range_rhs, depth + 1) {
                             Some(v) => v,
                             None => return None,
                         };
 
                         r_eval = rhs.eval();
                         let range_lhs = (self.minus_range.start+r_eval)..(self.minus_range.end+r_eval); // Renamed range to range_lhs
-                        lhs = match self.gen_iter(lnoprand, lnoprand-1, range_lhs) {
+                        lhs = match self.gen_iter_at(lnoprand, lnoprand-1, range_lhs, depth + 1) {
                             Some(v) => v,
                             None => return None,
                         };
                     },
                     _ => { // Add and default case
                         let range_lhs = try_option!(range_union(bound.clone(), self.single_range.clone())); // Renamed range to range_lhs
-                        lhs = match self.gen_iter(lnoprand, lnoprand-1, range_lhs) {
+                        lhs = match self.gen_iter_at(lnoprand, lnoprand-1, range_lhs, depth + 1) {
                             Some(v) => v,
                             None => return None,
                         };
 
                         l_eval = lhs.eval();
                         let range_rhs = (self.add_range.start-l_eval)..(self.add_range.end-l_eval); // Renamed range to range_rhs
-                        rhs = match self.gen_iter(rnoprand, rnoprand-1, range_rhs) {
+                        rhs = match self.gen_iter_at(rnoprand, rnoprand-1, range_rhs, depth + 1) {
                             Some(v) => v,
                             None => return None,
                         };
@@ -395,13 +497,34 @@ impl GenerativeMathGen {
     }
 
 
+    /// operator with the default weights of `ops`
     pub fn rand_op(&mut self) -> Op {
-        match self.rng.gen_range(0, 4) { 
-            3 => Op::Add,
-            1 => Op::Minus,
-            2 => Op::Mul,
-            0 => Op::Div,
-            _ => unreachable!(),
+        self.ops.default.pick(&mut self.rng)
+    }
+
+    /// operator for a node `depth` levels below the root
+    pub fn rand_op_at(&mut self, depth: usize) -> Op {
+        self.ops.pick(&mut self.rng, depth)
+    }
+
+    /// operator mix of the problems returned so far
+    pub fn op_stats(&self) -> &OpStats {
+        &self.stats
+    }
+
+    /// fill `template` if one is set, `None` when the ranges rule it out
+    fn fill_template(&mut self) -> Option<Expr> {
+        let t = self.template.clone()?;
+        let mut rng = self.rng.clone();
+        match t.fill(self, &mut rng) {
+            Some(e) => {
+                self.stats.record(&e);
+                Some(e)
+            }
+            None => {
+                warn!("template `{}` cannot be filled within the ranges, using a random shape", t);
+                None
+            }
         }
     }
 }
@@ -424,10 +547,20 @@ impl<G> MathPainter<G> where G: MathGenerator {
         cr.show_text(&msg);
     }
 
+    /// the next `n` problems from the generator, as a page would use them
+    pub fn problems(&mut self, n: usize) -> Vec<Expr> {
+        (0..n).map(|_| self.g.generate_rand_math()).collect()
+    }
 
-    //render vertical form calculation
-    pub fn render_vertical_form(&mut self, target: &Surface) {
-        let cr = Context::new(&target);
+    /// ends the page `cr` draws on and starts the next one, title and all
+    pub fn next_page(&self, target: &Surface, cr: &Context) -> Context {
+        cr.show_page();
+        self.page_context(target)
+    }
+
+    /// new context on `target` with the page title already drawn
+    pub fn page_context(&self, target: &Surface) -> Context {
+        let cr = Context::new(target);
         cr.set_antialias(Antialias::Subpixel);
         cr.set_source_rgb(0.0, 0.0, 0.0);
         cr.set_font_size(14.0);
@@ -436,6 +569,12 @@ impl<G> MathPainter<G> where G: MathGenerator {
         cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
         let title = format!("{}{}", " ".repeat(60), self.title);
         cr.show_text(title.as_str());
+        cr
+    }
+
+    //render vertical form calculation
+    pub fn render_vertical_form(&mut self, target: &Surface) {
+        let cr = self.page_context(target);
 
         let mut y = 70;
 
@@ -458,15 +597,7 @@ impl<G> MathPainter<G> where G: MathGenerator {
     }
 
     pub fn render_mental_form(&mut self, target: &Surface) {
-        let cr = Context::new(&target);
-        cr.set_antialias(Antialias::Subpixel);
-        cr.set_source_rgb(0.0, 0.0, 0.0);
-        cr.set_font_size(14.0);
-
-        cr.move_to(20.0, 40.0);
-        cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
-        let title = format!("{}{}", " ".repeat(60), self.title);
-        cr.show_text(title.as_str());
+        let cr = self.page_context(target);
 
         let mut y = 30;
 
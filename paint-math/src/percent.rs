@@ -0,0 +1,92 @@
+use mathgen::math::*;
+use mathgen::percent::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::ops::Range;
+
+/// Generates percent and ratio problems whose answers have at most
+/// `places` decimals.
+pub struct PercentGen {
+    pub places: u32,
+    /// wholes, starting values and totals
+    pub number_range: Range<i32>,
+    /// percents are multiples of this, taken as 1 to 50 so that at least
+    /// one percent below 100 is left
+    pub percent_step: i32,
+
+    rng: ThreadRng,
+}
+
+impl PercentGen {
+    pub fn new() -> Self {
+        PercentGen {
+            places: 0,
+            number_range: 20..500,
+            percent_step: 5,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn rand(&mut self, r: Range<i32>) -> i32 {
+        self.rng.gen_range(r.start, r.end)
+    }
+
+    fn percent(&mut self) -> i32 {
+        let step = self.percent_step.clamp(1, 50);
+        self.rand(1..100 / step) * step
+    }
+
+    pub fn problem(&mut self) -> PercentProblem {
+        loop {
+            let n = self.rand(self.number_range.clone());
+            let p = match self.rand(0..4) {
+                0 => PercentProblem::PercentOf { whole: n, percent: self.percent() },
+                1 => PercentProblem::Change {
+                    from: n,
+                    percent: self.percent(),
+                    increase: self.rng.gen(),
+                },
+                2 => {
+                    let percent = self.percent();
+                    PercentProblem::WhatPercent { part: n * percent / 100, whole: n }
+                }
+                _ => PercentProblem::Split {
+                    total: n,
+                    ratio: (self.rand(1..10), self.rand(1..10)),
+                },
+            };
+            let positive = matches!(p.answers(), Some(a) if a.iter().all(|v| v.numer() > 0));
+            if positive && p.clean(self.places).is_some() {
+                return p;
+            }
+        }
+    }
+}
+
+impl Default for PercentGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// percent and ratio page, one problem a row with room for the working,
+    /// `filled` adds the answers
+    pub fn render_percent_problems(&mut self, target: &Surface, problems: &[PercentProblem], places: u32, filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
+
+        let mut y = 90.0;
+        for (i, p) in problems.iter().enumerate() {
+            cr.move_to(30.0, y);
+            cr.show_text(&format!("{}. {}", i + 1, p));
+            if let Some(answer) = p.answer_text(places).filter(|_| filled) {
+                cr.move_to(60.0, y + 24.0);
+                cr.show_text(&format!("答: {}", answer));
+            }
+            y += 60.0;
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use mathgen::math::*;
+use mathgen::points::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::f64::consts::PI;
+use std::ops::Range;
+
+const CARD_WIDTH: f64 = 50.0;
+const CARD_HEIGHT: f64 = 70.0;
+const CARD_GAP: f64 = 12.0;
+const CORNER: f64 = 6.0;
+/// hands dealt before giving up on the solution count
+const MAX_DEALS: usize = 10000;
+
+/// Deals 24 game hands. The number of distinct solutions is kept within
+/// `solutions`, so `1..2` gives hard hands with exactly one.
+pub struct PointsGen {
+    pub target: i32,
+    /// card values, ace is 1 and king 13
+    pub cards: Range<i32>,
+    pub solutions: Range<usize>,
+
+    rng: ThreadRng,
+}
+
+impl PointsGen {
+    pub fn new() -> Self {
+        PointsGen {
+            target: 24,
+            cards: 1..14,
+            solutions: 1..usize::MAX,
+            rng: thread_rng(),
+        }
+    }
+
+    /// hands with a single solution
+    pub fn hard() -> Self {
+        PointsGen {
+            solutions: 1..2,
+            ..Self::new()
+        }
+    }
+
+    /// `None` when no hand dealt had a solution count in `solutions`
+    pub fn hand(&mut self) -> Option<Hand> {
+        if self.cards.start >= self.cards.end {
+            return None;
+        }
+        (0..MAX_DEALS).find_map(|_| {
+            let cards = (0..4)
+                .map(|_| self.rng.gen_range(self.cards.start, self.cards.end))
+                .collect();
+            let hand = Hand { cards, target: self.target };
+            Some(hand).filter(|h| self.solutions.contains(&h.solutions().len()))
+        })
+    }
+
+    pub fn hands(&mut self, n: usize) -> Option<Vec<Hand>> {
+        (0..n).map(|_| self.hand()).collect()
+    }
+}
+
+impl Default for PointsGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// rank printed in the corners: A, 2..10, J, Q, K
+fn rank(card: i32) -> String {
+    match card {
+        1 => "A".to_string(),
+        11 => "J".to_string(),
+        12 => "Q".to_string(),
+        13 => "K".to_string(),
+        v => v.to_string(),
+    }
+}
+
+fn rounded_rect(cr: &Context, x: f64, y: f64, w: f64, h: f64, r: f64) {
+    cr.new_path();
+    cr.arc(x + w - r, y + r, r, -PI / 2.0, 0.0);
+    cr.arc(x + w - r, y + h - r, r, 0.0, PI / 2.0);
+    cr.arc(x + r, y + h - r, r, PI / 2.0, PI);
+    cr.arc(x + r, y + r, r, PI, 3.0 * PI / 2.0);
+    cr.close_path();
+    cr.stroke();
+}
+
+/// playing card with its top left corner at (x, y): rank in the corners,
+/// value in the middle
+pub fn draw_card(cr: &Context, x: f64, y: f64, card: i32) {
+    cr.set_line_width(1.0);
+    rounded_rect(cr, x, y, CARD_WIDTH, CARD_HEIGHT, CORNER);
+
+    cr.save();
+    cr.set_font_size(10.0);
+    let r = rank(card);
+    cr.move_to(x + 4.0, y + 12.0);
+    cr.show_text(&r);
+    let ext = cr.text_extents(&r);
+    cr.move_to(x + CARD_WIDTH - 4.0 - ext.x_advance, y + CARD_HEIGHT - 4.0);
+    cr.show_text(&r);
+
+    cr.set_font_size(22.0);
+    let v = card.to_string();
+    let ext = cr.text_extents(&v);
+    cr.move_to(
+        x + (CARD_WIDTH - ext.width) / 2.0 - ext.x_bearing,
+        y + (CARD_HEIGHT - ext.height) / 2.0 - ext.y_bearing,
+    );
+    cr.show_text(&v);
+    cr.restore();
+}
+
+/// the cards of `hand` in a row from (x, y), `filled` writes the first
+/// solution below them as the answer key
+pub fn draw_hand(cr: &Context, x: f64, y: f64, hand: &Hand, filled: bool) {
+    for (i, &c) in hand.cards.iter().enumerate() {
+        draw_card(cr, x + i as f64 * (CARD_WIDTH + CARD_GAP), y, c);
+    }
+
+    let line_y = y + CARD_HEIGHT + 22.0;
+    cr.move_to(x, line_y);
+    match hand.solutions().first() {
+        Some(e) if filled => cr.show_text(&format!("{}={}", e, hand.target)),
+        _ => cr.show_text(&format!("{}={}", "_".repeat(16), hand.target)),
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// 24 game page, two hands a row
+    pub fn render_points(&mut self, target: &Surface, hands: &[Hand], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 70.0;
+        for chunk in hands.chunks(2) {
+            for (i, h) in chunk.iter().enumerate() {
+                draw_hand(&cr, 40.0 + i as f64 * 280.0, y, h, filled);
+            }
+            y += CARD_HEIGHT + 55.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_hands_have_one_solution() {
+        let mut g = PointsGen::hard();
+        for h in g.hands(3).unwrap() {
+            assert_eq!(h.solutions().len(), 1, "{}", h);
+        }
+    }
+}
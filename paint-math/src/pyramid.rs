@@ -0,0 +1,108 @@
+use mathgen::enumerate::*;
+use mathgen::math::*;
+use mathgen::pyramid::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+
+const BRICK_WIDTH: f64 = 48.0;
+const BRICK_HEIGHT: f64 = 28.0;
+/// pyramids built before giving up on the rules
+const MAX_TRIES: usize = 10000;
+
+/// Builds number pyramids within the ranges of `rules`, e.g. a
+/// `PrimitiveMathGen` or a `Natural`: the base comes from its operands and
+/// every brick must be a step it allows with a result it accepts.
+pub struct PyramidGen<R: Rules> {
+    pub rules: R,
+    /// bricks in the bottom row
+    pub base: usize,
+    /// `Add` or `Mul`
+    pub op: Op,
+    pub hidden: usize,
+
+    rng: ThreadRng,
+}
+
+impl<R: Rules> PyramidGen<R> {
+    pub fn new(rules: R) -> Self {
+        PyramidGen {
+            rules,
+            base: 4,
+            op: Op::Add,
+            hidden: 6,
+            rng: thread_rng(),
+        }
+    }
+
+    /// `None` when no pyramid kept to the rules with that many hidden, or
+    /// when `op` is neither `Add` nor `Mul`
+    pub fn pyramid(&mut self) -> Option<Pyramid> {
+        if self.hidden > self.base * (self.base + 1) / 2 || !matches!(self.op, Op::Add | Op::Mul) {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| Pyramid::random(&self.rules, self.base, self.op, self.hidden, &mut self.rng))
+    }
+
+    pub fn pyramids(&mut self, n: usize) -> Option<Vec<Pyramid>> {
+        (0..n).map(|_| self.pyramid()).collect()
+    }
+}
+
+/// the pyramid with its top brick centred on x and its top edge at y, the
+/// operator in the corner, hidden bricks left empty unless `filled`
+pub fn draw_pyramid(cr: &Context, x: f64, y: f64, p: &Pyramid, filled: bool) {
+    cr.set_line_width(1.0);
+    let shown = p.shown();
+    let height = p.rows.len();
+
+    for (k, row) in p.rows.iter().enumerate() {
+        let top = y + (height - 1 - k) as f64 * BRICK_HEIGHT;
+        let left = x - row.len() as f64 * BRICK_WIDTH / 2.0;
+        for (i, v) in row.iter().enumerate() {
+            let bx = left + i as f64 * BRICK_WIDTH;
+            cr.rectangle(bx, top, BRICK_WIDTH, BRICK_HEIGHT);
+            cr.stroke();
+            if shown[k][i].is_none() && !filled {
+                continue;
+            }
+
+            let s = v.to_string();
+            let ext = cr.text_extents(&s);
+            cr.move_to(
+                bx + (BRICK_WIDTH - ext.width) / 2.0 - ext.x_bearing,
+                top + (BRICK_HEIGHT - ext.height) / 2.0 - ext.y_bearing,
+            );
+            cr.show_text(&s);
+        }
+    }
+
+    cr.save();
+    cr.set_font_size(12.0);
+    cr.move_to(x - p.rows[0].len() as f64 * BRICK_WIDTH / 2.0, y + 12.0);
+    cr.show_text(&p.op.to_string());
+    cr.restore();
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// number pyramid pages, two pyramids a row
+    pub fn render_pyramids(&mut self, target: &Surface, pyramids: &[Pyramid], filled: bool) {
+        let mut cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 70.0;
+        for chunk in pyramids.chunks(2) {
+            let height = chunk.iter().map(|p| p.rows.len()).max().unwrap_or(0) as f64 * BRICK_HEIGHT;
+            if y > 70.0 && y + height > PAGE_BOTTOM {
+                cr = self.next_page(target, &cr);
+                cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+                y = 70.0;
+            }
+            for (i, p) in chunk.iter().enumerate() {
+                draw_pyramid(&cr, 155.0 + i as f64 * 285.0, y, p, filled);
+            }
+            y += height + 30.0;
+        }
+    }
+}
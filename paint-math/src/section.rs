@@ -0,0 +1,34 @@
+use mathgen::magic::GridPuzzle;
+use mathgen::math::*;
+
+use crate::paint::*;
+use cairo::*;
+
+/// One page of a worksheet. `Mental` and `Vertical` draw fresh problems
+/// from the painter's generator, `GridPuzzles` holds puzzles made
+/// beforehand so the same section can be printed again with the answers.
+pub enum Section {
+    Mental,
+    Vertical,
+    GridPuzzles(Vec<GridPuzzle>),
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    pub fn render_section(&mut self, target: &Surface, section: &Section, filled: bool) {
+        match section {
+            Section::Mental => self.render_mental_form(target),
+            Section::Vertical => self.render_vertical_form(target),
+            Section::GridPuzzles(v) => self.render_grid_puzzles(target, v, filled),
+        }
+    }
+
+    /// every section on a page of its own in one pdf, `filled` gives the
+    /// answer key
+    pub fn render_worksheet_pdf<T: AsRef<str>>(&mut self, name: T, sections: &[Section], filled: bool) {
+        let target = pdf::File::new(8.3 * 72.0, 11.7 * 72.0, name.as_ref());
+        for s in sections {
+            self.render_section(&target, s, filled);
+            Context::new(&target).show_page();
+        }
+    }
+}
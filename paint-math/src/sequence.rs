@@ -0,0 +1,137 @@
+use mathgen::math::*;
+use mathgen::sequence::*;
+
+use crate::paint::*;
+use cairo::*;
+use rand::prelude::*;
+use std::ops::Range;
+
+/// rules tried before giving up on the length and blanks asked for
+const MAX_TRIES: usize = 10000;
+
+/// Generates number pattern problems. `difficulty` 1 keeps to counting up
+/// by a fixed step with the blanks at the end, 2 adds counting down,
+/// doubling and alternating steps with blanks in the middle, 3 adds
+/// formulas of the position and blanks anywhere.
+pub struct SequenceGen {
+    pub difficulty: u8,
+    /// terms shown, blanks included
+    pub length: usize,
+    pub blanks: usize,
+
+    rng: ThreadRng,
+}
+
+impl SequenceGen {
+    pub fn new(difficulty: u8) -> Self {
+        SequenceGen {
+            difficulty,
+            length: 6,
+            blanks: 2,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn rand(&mut self, r: Range<i32>) -> i32 {
+        self.rng.gen_range(r.start, r.end)
+    }
+
+    fn rule(&mut self) -> Rule {
+        let kind = match self.difficulty {
+            0 | 1 => 0,
+            2 => self.rand(0..4),
+            _ => self.rand(0..6),
+        };
+        match kind {
+            0 => Rule::Arithmetic {
+                start: self.rand(1..20),
+                step: self.rand(1..10),
+            },
+            1 => Rule::Arithmetic {
+                start: self.rand(50..100),
+                step: -self.rand(2..10),
+            },
+            2 => Rule::Geometric {
+                start: self.rand(1..6),
+                ratio: self.rand(2..4),
+            },
+            3 => Rule::Alternating {
+                start: self.rand(1..10),
+                steps: (self.rand(1..10), self.rand(1..10)),
+            },
+            4 => Rule::Alternating {
+                start: self.rand(10..30),
+                steps: (self.rand(3..10), -self.rand(1..3)),
+            },
+            _ => {
+                use Formula::*;
+                let k = self.rand(1..5);
+                let square = || Formula::node(Op::Mul, N, N);
+                let formula = [
+                    square(),
+                    Formula::node(Op::Add, square(), Number(k)),
+                    Formula::node(Op::Mul, N, Formula::node(Op::Add, N, Number(1))),
+                    Formula::node(Op::Mul, square(), Number(k + 1)),
+                ];
+                Rule::Formula(formula.choose(&mut self.rng).expect("formulas").clone())
+            }
+        }
+    }
+
+    fn blank_positions(&mut self) -> Vec<usize> {
+        let len = self.length;
+        let from = match self.difficulty {
+            0 | 1 => len.saturating_sub(self.blanks),
+            2 => 1,
+            _ => 0,
+        };
+        let mut positions = (from..len).collect::<Vec<_>>();
+        positions.shuffle(&mut self.rng);
+        positions.truncate(self.blanks);
+        positions
+    }
+
+    /// `None` when no pattern settled on a single rule, as when fewer than
+    /// three terms are left visible
+    pub fn problem(&mut self) -> Option<SequenceProblem> {
+        if self.length < self.blanks + 3 {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| {
+            let rule = self.rule();
+            let blanks = self.blank_positions();
+            SequenceProblem::new(rule, self.length, &blanks)
+        })
+    }
+}
+
+/// the terms at (x, y) in boxes-to-fill, `filled` writes the answers in and
+/// the rule after them
+pub fn draw_sequence(cr: &Context, x: f64, y: f64, p: &SequenceProblem, filled: bool) {
+    let line = p.show("(    )", filled).join(",  ");
+    cr.move_to(x, y);
+    cr.show_text(&line);
+
+    if filled {
+        cr.save();
+        cr.select_font_face("Noto Sans CJK JP", FontSlant::Normal, FontWeight::Normal);
+        cr.set_font_size(11.0);
+        cr.move_to(x + 20.0, y + 18.0);
+        cr.show_text(&format!("规律: {}", p.rule));
+        cr.restore();
+    }
+}
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// number pattern page, one sequence a row
+    pub fn render_sequences(&mut self, target: &Surface, problems: &[SequenceProblem], filled: bool) {
+        let cr = self.page_context(target);
+        cr.select_font_face("mono", FontSlant::Normal, FontWeight::Bold);
+
+        let mut y = 90.0;
+        for p in problems {
+            draw_sequence(&cr, 40.0, y, p, filled);
+            y += 45.0;
+        }
+    }
+}
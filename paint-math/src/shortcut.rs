@@ -0,0 +1,161 @@
+use mathgen::math::Expr::*;
+use mathgen::math::*;
+use mathgen::shortcut::*;
+
+use rand::prelude::*;
+use std::ops::Range;
+
+/// Generates problems built around one of `strategies`, each of them has a
+/// shortcut that `detect` finds for the answer key.
+pub struct ShortcutMathGen {
+    pub strategies: Vec<Strategy>,
+    /// range of the plain numbers mixed in with the friendly ones
+    pub single_range: Range<i32>,
+
+    rng: ThreadRng,
+}
+
+impl ShortcutMathGen {
+    pub fn new() -> Self {
+        ShortcutMathGen {
+            strategies: vec![
+                Strategy::RegroupProduct,
+                Strategy::RegroupSum,
+                Strategy::SplitFactor,
+                Strategy::FactorOut,
+                Strategy::Expand,
+                Strategy::SubtractSum,
+                Strategy::GroupSubtrahends,
+                Strategy::FriendlyPair,
+            ],
+            single_range: 11..100,
+            rng: thread_rng(),
+        }
+    }
+
+    pub fn rand(&mut self, r: Range<i32>) -> i32 {
+        self.rng.gen_range(r.start, r.end)
+    }
+
+    /// number from `single_range` that is not a multiple of ten
+    fn plain(&mut self) -> i32 {
+        loop {
+            let v = self.rand(self.single_range.clone());
+            if v % 10 != 0 {
+                return v;
+            }
+        }
+    }
+
+    /// like `plain` but also prime to ten, so it never rounds up 25 or 125
+    fn odd(&mut self) -> i32 {
+        loop {
+            let v = self.plain();
+            if v % 2 != 0 && v % 5 != 0 {
+                return v;
+            }
+        }
+    }
+
+    pub fn gen_strategy(&mut self, strategy: Strategy) -> Expr {
+        let b = Box::new;
+        match strategy {
+            Strategy::RegroupProduct => {
+                let (f, partner) = [(25, 4), (125, 8), (25, 8)][self.rand(0..3) as usize];
+                let x = self.odd();
+                Compound(Op::Mul, b(Primitive(Op::Mul, f, x)), b(Single(partner)))
+            }
+            Strategy::RegroupSum => {
+                let a = self.plain();
+                let round = (a / 100 + 1) * 100;
+                let x = loop {
+                    let x = self.plain();
+                    if (a + x) % 10 != 0 {
+                        break x;
+                    }
+                };
+                Compound(Op::Add, b(Primitive(Op::Add, a, x)), b(Single(round - a)))
+            }
+            Strategy::SplitFactor => {
+                let (f, partner) = [(25, 4), (125, 8)][self.rand(0..2) as usize];
+                let k = loop {
+                    let k = self.rand(3..13);
+                    if k % 5 != 0 {
+                        break k;
+                    }
+                };
+                Primitive(Op::Mul, f, partner * k)
+            }
+            Strategy::FactorOut => {
+                let common = self.plain();
+                if self.rand(0..2) == 0 {
+                    let x = self.rand(1..10);
+                    let near = 100 - x;
+                    Compound(Op::Add, b(Primitive(Op::Mul, near, common)), b(Primitive(Op::Mul, x, common)))
+                } else {
+                    let x = self.rand(1..10);
+                    Compound(Op::Minus, b(Primitive(Op::Mul, common, 100 + x)), b(Primitive(Op::Mul, common, x)))
+                }
+            }
+            Strategy::Expand => {
+                let near = [98, 99, 101, 102][self.rand(0..4) as usize];
+                Primitive(Op::Mul, self.plain(), near)
+            }
+            Strategy::SubtractSum => {
+                let round = self.rand(2..10) * 100;
+                let first = self.rand(100..round);
+                let second = self.rand(1..round / 2);
+                Compound(Op::Minus, b(Single(first + round)), b(Primitive(Op::Add, first, second)))
+            }
+            Strategy::GroupSubtrahends => {
+                let second = self.plain();
+                let round = (second / 100 + 1) * 100;
+                let first = self.rand(round..round * 3);
+                Compound(Op::Minus, b(Primitive(Op::Minus, first + round, second)), b(Single(round - second)))
+            }
+            Strategy::FriendlyPair => {
+                let (f, partner) = [(25, 4), (125, 8)][self.rand(0..2) as usize];
+                if self.rand(0..2) == 0 {
+                    Primitive(Op::Mul, f, partner)
+                } else {
+                    Primitive(Op::Mul, partner, f)
+                }
+            }
+        }
+    }
+}
+
+impl Default for ShortcutMathGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MathGenerator for ShortcutMathGen {
+    fn generate_rand_math(&mut self) -> Expr {
+        let i = self.rand(0..self.strategies.len() as i32) as usize;
+        self.gen_strategy(self.strategies[i])
+    }
+
+    /// the shape follows the strategy, `noprand` and `nop` are ignored
+    fn gen(&mut self, _noprand: i32, _nop: i32) -> Expr {
+        self.generate_rand_math()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_shortcuts_are_detected() {
+        let mut g = ShortcutMathGen::new();
+        for s in g.strategies.clone() {
+            for _ in 0..200 {
+                let e = g.gen_strategy(s);
+                let found = detect(&e).unwrap_or_else(|| panic!("no shortcut for {}", e));
+                assert_eq!(found.rewritten.eval(), e.eval(), "{}", found);
+            }
+        }
+    }
+}
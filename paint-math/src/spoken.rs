@@ -0,0 +1,19 @@
+use mathgen::math::*;
+use mathgen::spoken::*;
+
+use crate::paint::*;
+use std::fs;
+use std::io;
+
+impl<G> MathPainter<G> where G: MathGenerator {
+    /// a dictation worksheet of `count` problems read aloud, for a
+    /// text-to-speech tool
+    pub fn dictation(&mut self, count: usize, lang: Language, format: ScriptFormat) -> String {
+        script(&self.problems(count), lang, format)
+    }
+
+    /// `dictation` written to `name` as is, no extension is added for `format`
+    pub fn export_dictation<T: AsRef<str>>(&mut self, name: T, count: usize, lang: Language, format: ScriptFormat) -> io::Result<()> {
+        fs::write(name.as_ref(), self.dictation(count, lang, format))
+    }
+}
@@ -0,0 +1,121 @@
+use mathgen::enumerate::*;
+use mathgen::math::*;
+
+use crate::paint::PrimitiveMathGen;
+use rand::prelude::*;
+use std::fmt;
+
+/// trees `UniformMathGen` builds at most while listing, more would not fit
+/// in memory
+pub const MAX_PROBLEMS: u64 = 1_000_000;
+
+/// listing the problems takes this many trees, more than `MAX_PROBLEMS`,
+/// counting those the rules throw out at the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyProblems(pub u64);
+
+impl fmt::Display for TooManyProblems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} trees to build, at most {} fit", self.0, MAX_PROBLEMS)
+    }
+}
+
+/// Draws uniformly from every problem a set of `Rules` allows. The whole
+/// problem space is listed up front, so the ranges must be small: past
+/// `MAX_PROBLEMS` the constructors refuse.
+pub struct UniformMathGen {
+    problems: Vec<Expr>,
+    rng: ThreadRng,
+}
+
+impl UniformMathGen {
+    /// every valid problem with `leaves` operands, of any shape
+    pub fn new<R: Rules>(rules: &R, leaves: usize) -> Result<Self, TooManyProblems> {
+        Self::bounded(count_built_all(rules, leaves), || list_all(rules, leaves))
+    }
+
+    pub fn with_shape<R: Rules>(rules: &R, shape: &Shape) -> Result<Self, TooManyProblems> {
+        Self::bounded(count_built(rules, shape), || list(rules, shape))
+    }
+
+    /// the problems `gen` would pick from at its level
+    pub fn from_primitive(gen: &PrimitiveMathGen) -> Result<Self, TooManyProblems> {
+        Self::new(gen, (gen.level + 1) as usize)
+    }
+
+    /// counting is cheap, so only list when the trees to build fit
+    fn bounded<F: FnOnce() -> Vec<Expr>>(count: u64, list: F) -> Result<Self, TooManyProblems> {
+        if count > MAX_PROBLEMS {
+            return Err(TooManyProblems(count));
+        }
+        Ok(UniformMathGen {
+            problems: list(),
+            rng: thread_rng(),
+        })
+    }
+
+    /// number of distinct problems
+    pub fn len(&self) -> usize {
+        self.problems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn problems(&self) -> &[Expr] {
+        &self.problems
+    }
+
+    /// `n` distinct problems, `None` if there are not that many
+    pub fn unique(&mut self, n: usize) -> Option<Vec<Expr>> {
+        if n > self.problems.len() {
+            return None;
+        }
+        Some(self.problems.choose_multiple(&mut self.rng, n).cloned().collect())
+    }
+}
+
+impl MathGenerator for UniformMathGen {
+    fn generate_rand_math(&mut self) -> Expr {
+        self.problems
+            .choose(&mut self.rng)
+            .cloned()
+            .expect("no valid problem in this configuration")
+    }
+
+    /// the operand count is fixed when the problems are listed
+    fn gen(&mut self, _noprand: i32, _nop: i32) -> Expr {
+        self.generate_rand_math()
+    }
+}
+
+impl PrimitiveMathGen {
+    /// how many distinct problems `generate_rand_math` can produce
+    pub fn count_problems(&self) -> u64 {
+        count_all(self, (self.level + 1) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_large_problem_spaces() {
+        let mut gen = PrimitiveMathGen::new();
+        let built = count_built_all(&gen, 4);
+        assert_eq!(UniformMathGen::from_primitive(&gen).err(), Some(TooManyProblems(built)));
+
+        // few problems pass, but every tree is built before the check
+        gen.level = 2;
+        gen.result_range = 10..12;
+        assert!(gen.count_problems() < MAX_PROBLEMS);
+        assert!(UniformMathGen::from_primitive(&gen).is_err());
+
+        gen.level = 1;
+        gen.single_range = 2..10;
+        let u = UniformMathGen::from_primitive(&gen).unwrap();
+        assert_eq!(u.len() as u64, gen.count_problems());
+    }
+}